@@ -0,0 +1,72 @@
+//! Check that every level in the LDtk project can be beaten, without opening a window.
+//!
+//! Run with `cargo run --bin check_levels [path/to/project.ldtk]`.
+//! Exits with an error if any level is impossible or trivial.
+
+use bevy_ecs_ldtk::ldtk::{LdtkJson, Level};
+use shipping_friends::level_check::check_level;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const DEFAULT_PROJECT: &str = "assets/levels/maps.ldtk";
+
+fn main() -> ExitCode {
+    let path = std::env::args()
+        .nth(1)
+        .map_or_else(|| PathBuf::from(DEFAULT_PROJECT), PathBuf::from);
+    let project: LdtkJson = match read_json(&path) {
+        Ok(project) => project,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut problems = 0;
+    let levels = project
+        .levels
+        .iter()
+        .chain(project.worlds.iter().flat_map(|world| &world.levels));
+    for level in levels {
+        let level = match load_layers(&path, level) {
+            Ok(level) => level,
+            Err(e) => {
+                eprintln!("{}: {e}", level.identifier);
+                problems += 1;
+                continue;
+            }
+        };
+        let check = check_level(&level);
+        if !check.is_possible() || check.is_trivial() {
+            problems += 1;
+        }
+        print!("{check}");
+    }
+
+    if problems > 0 {
+        eprintln!("{problems} level(s) need attention");
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Levels saved in separate files only list their layers there.
+fn load_layers(project_path: &Path, level: &Level) -> Result<Level, String> {
+    match (&level.layer_instances, &level.external_rel_path) {
+        (None, Some(rel_path)) => {
+            let level_path = project_path
+                .parent()
+                .unwrap_or(Path::new(""))
+                .join(rel_path);
+            read_json(&level_path)
+        }
+        _ => Ok(level.clone()),
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| format!("Couldn't read {}: {e}", path.display()))?;
+    serde_json::from_str(&json).map_err(|e| format!("Couldn't parse {}: {e}", path.display()))
+}
@@ -1,5 +1,90 @@
+use crate::AppSet;
 use bevy::prelude::*;
 
 pub(super) fn plugin(app: &mut App) {
-    // TODO: Implement animations :)
+    app.register_type::<(TranslationTween, RotationTween)>();
+    app.add_systems(
+        Update,
+        (
+            (tick_translation_tweens, tick_rotation_tweens).in_set(AppSet::TickTimers),
+            (apply_translation_tweens, apply_rotation_tweens).in_set(AppSet::Update),
+        ),
+    );
+}
+
+/// Smoothly move an entity's translation between two points.
+/// Inserting a new tween replaces the current one, so it will start from wherever
+/// the entity is at that moment.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct TranslationTween {
+    from: Vec3,
+    to: Vec3,
+    timer: Timer,
+}
+
+impl TranslationTween {
+    pub fn new(from: Vec3, to: Vec3, duration: f32) -> Self {
+        Self {
+            from,
+            to,
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+        }
+    }
+}
+
+/// Smoothly rotate an entity between two orientations.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct RotationTween {
+    from: Quat,
+    to: Quat,
+    timer: Timer,
+}
+
+impl RotationTween {
+    pub fn new(from: Quat, to: Quat, duration: f32) -> Self {
+        Self {
+            from,
+            to,
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+        }
+    }
+}
+
+/// Ease in and out so that consecutive steps don't look jerky.
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn tick_translation_tweens(time: Res<Time>, mut query: Query<&mut TranslationTween>) {
+    for mut tween in &mut query {
+        tween.timer.tick(time.delta());
+    }
+}
+
+fn tick_rotation_tweens(time: Res<Time>, mut query: Query<&mut RotationTween>) {
+    for mut tween in &mut query {
+        tween.timer.tick(time.delta());
+    }
+}
+
+fn apply_translation_tweens(mut query: Query<(&TranslationTween, &mut Transform)>) {
+    for (tween, mut transform) in &mut query {
+        if tween.timer.finished() && !tween.timer.just_finished() {
+            continue;
+        }
+        let t = smoothstep(tween.timer.fraction());
+        transform.translation = tween.from.lerp(tween.to, t);
+    }
+}
+
+fn apply_rotation_tweens(mut query: Query<(&RotationTween, &mut Transform)>) {
+    for (tween, mut transform) in &mut query {
+        if tween.timer.finished() && !tween.timer.just_finished() {
+            continue;
+        }
+        let t = smoothstep(tween.timer.fraction());
+        transform.rotation = tween.from.slerp(tween.to, t);
+    }
 }
@@ -1,11 +1,30 @@
+//! Every asset the game uses, listed by key in `assets/game.manifest.ron`.
+//! The manifest fills in each [`HandleMap`] once it has loaded, and again whenever it changes.
+
 use bevy::{
+    asset::{
+        io::Reader, AssetLoader, AssetPath, AsyncReadExt, LoadContext, LoadState,
+        RecursiveDependencyLoadState,
+    },
+    ecs::system::SystemParam,
     prelude::*,
     render::texture::{ImageLoaderSettings, ImageSampler},
     utils::HashMap,
 };
 use bevy_ecs_ldtk::assets::LdtkProject;
+use serde::{de::DeserializeOwned, Deserialize};
+use std::collections::HashMap as StdHashMap;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use crate::game::audio::sfx::SfxTable;
 
 pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<AssetManifest>();
+    app.register_asset_loader(RonAssetLoader::<AssetManifest>::new(&["manifest.ron"]));
+    app.init_resource::<AssetManifestHandle>();
+    app.add_systems(PreUpdate, apply_asset_manifest);
+
     app.register_type::<HandleMap<ImageKey>>();
     app.init_resource::<HandleMap<ImageKey>>();
 
@@ -15,6 +34,9 @@ pub(super) fn plugin(app: &mut App) {
     app.register_type::<HandleMap<SfxKey>>();
     app.init_resource::<HandleMap<SfxKey>>();
 
+    app.register_type::<HandleMap<SfxTableKey>>();
+    app.init_resource::<HandleMap<SfxTableKey>>();
+
     app.register_type::<HandleMap<SoundtrackKey>>();
     app.init_resource::<HandleMap<SoundtrackKey>>();
 
@@ -22,7 +44,7 @@ pub(super) fn plugin(app: &mut App) {
     app.init_resource::<HandleMap<LdtkKey>>();
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Reflect, Deserialize)]
 pub enum ImageKey {
     Ducky,
     BasicTileSet,
@@ -31,14 +53,14 @@ pub enum ImageKey {
     OverlayMarker,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Reflect, Deserialize)]
 pub enum AtlasLayoutKey {
     BasicTileSet,
     BulkLoadVessel,
     LoadingCrane,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Reflect, Deserialize)]
 pub enum LdtkKey {
     Main,
 }
@@ -54,100 +76,7 @@ impl AssetKey for LdtkKey {
     type Asset = LdtkProject;
 }
 
-impl FromWorld for HandleMap<ImageKey> {
-    fn from_world(world: &mut World) -> Self {
-        let asset_server = world.resource::<AssetServer>();
-        [
-            (
-                ImageKey::Ducky,
-                asset_server.load_with_settings(
-                    "images/ducky.png",
-                    |settings: &mut ImageLoaderSettings| {
-                        settings.sampler = ImageSampler::nearest();
-                    },
-                ),
-            ),
-            (
-                ImageKey::BasicTileSet,
-                asset_server.load_with_settings(
-                    "images/basic_tiles.png",
-                    |settings: &mut ImageLoaderSettings| {
-                        settings.sampler = ImageSampler::nearest();
-                    },
-                ),
-            ),
-            (
-                ImageKey::BulkLoadVessel,
-                asset_server.load_with_settings(
-                    "images/bulk_load_vessel.png",
-                    |settings: &mut ImageLoaderSettings| {
-                        settings.sampler = ImageSampler::nearest();
-                    },
-                ),
-            ),
-            (
-                ImageKey::LoadingCrane,
-                asset_server.load_with_settings(
-                    "images/ship_loading_crane_smol.png",
-                    |settings: &mut ImageLoaderSettings| {
-                        settings.sampler = ImageSampler::nearest();
-                    },
-                ),
-            ),
-            (
-                ImageKey::OverlayMarker,
-                asset_server.load_with_settings(
-                    "images/overlay_marker.png",
-                    |settings: &mut ImageLoaderSettings| {
-                        settings.sampler = ImageSampler::nearest();
-                    },
-                ),
-            ),
-        ]
-        .into()
-    }
-}
-
-impl FromWorld for HandleMap<AtlasLayoutKey> {
-    fn from_world(world: &mut World) -> Self {
-        let mut texture_atlas_layouts = world.resource_mut::<Assets<TextureAtlasLayout>>();
-        [
-            (
-                AtlasLayoutKey::BasicTileSet,
-                texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
-                    UVec2::splat(100),
-                    2,
-                    1,
-                    None,
-                    None,
-                )),
-            ),
-            (
-                AtlasLayoutKey::BulkLoadVessel,
-                texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
-                    UVec2::new(200, 80),
-                    9,
-                    1,
-                    None,
-                    None,
-                )),
-            ),
-            (
-                AtlasLayoutKey::LoadingCrane,
-                texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
-                    UVec2::splat(100),
-                    6,
-                    1,
-                    None,
-                    None,
-                )),
-            ),
-        ]
-        .into()
-    }
-}
-
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Reflect, Deserialize)]
 pub enum SfxKey {
     ButtonHover,
     ButtonPress,
@@ -155,65 +84,38 @@ pub enum SfxKey {
     Step2,
     Step3,
     Step4,
+    // The gameplay sounds below still point at the step and button sounds in the
+    // manifest, until their own sounds are recorded.
+    ShipMove,
+    ShipRotate,
+    WallBump,
+    ShipBump,
+    CraneLoad,
+    CycleFinish,
+    ContractComplete,
 }
 
 impl AssetKey for SfxKey {
     type Asset = AudioSource;
 }
 
-impl FromWorld for HandleMap<SfxKey> {
-    fn from_world(world: &mut World) -> Self {
-        let asset_server = world.resource::<AssetServer>();
-        [
-            (
-                SfxKey::ButtonHover,
-                asset_server.load("audio/sfx/button_hover.ogg"),
-            ),
-            (
-                SfxKey::ButtonPress,
-                asset_server.load("audio/sfx/button_press.ogg"),
-            ),
-            (SfxKey::Step1, asset_server.load("audio/sfx/step1.ogg")),
-            (SfxKey::Step2, asset_server.load("audio/sfx/step2.ogg")),
-            (SfxKey::Step3, asset_server.load("audio/sfx/step3.ogg")),
-            (SfxKey::Step4, asset_server.load("audio/sfx/step4.ogg")),
-        ]
-        .into()
-    }
-}
-
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
-pub enum SoundtrackKey {
-    Menus,
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Reflect, Deserialize)]
+pub enum SfxTableKey {
     Gameplay,
 }
 
-impl AssetKey for SoundtrackKey {
-    type Asset = AudioSource;
+impl AssetKey for SfxTableKey {
+    type Asset = SfxTable;
 }
 
-impl FromWorld for HandleMap<SoundtrackKey> {
-    fn from_world(world: &mut World) -> Self {
-        let asset_server = world.resource::<AssetServer>();
-        [
-            (
-                SoundtrackKey::Menus,
-                asset_server.load("audio/soundtracks/Monkeys Spinning Monkeys.ogg"),
-            ),
-            (
-                SoundtrackKey::Gameplay,
-                asset_server.load("audio/soundtracks/Fluffing A Duck.ogg"),
-            ),
-        ]
-        .into()
-    }
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Reflect, Deserialize)]
+pub enum SoundtrackKey {
+    Menus,
+    Gameplay,
 }
 
-impl FromWorld for HandleMap<LdtkKey> {
-    fn from_world(world: &mut World) -> Self {
-        let asset_server = world.resource::<AssetServer>();
-        [(LdtkKey::Main, asset_server.load("levels/maps.ldtk"))].into()
-    }
+impl AssetKey for SoundtrackKey {
+    type Asset = AudioSource;
 }
 
 pub trait AssetKey: Sized {
@@ -224,6 +126,12 @@ pub trait AssetKey: Sized {
 #[reflect(Resource)]
 pub struct HandleMap<K: AssetKey>(HashMap<K, Handle<K::Asset>>);
 
+impl<K: AssetKey> Default for HandleMap<K> {
+    fn default() -> Self {
+        Self(HashMap::default())
+    }
+}
+
 impl<K: AssetKey, T> From<T> for HandleMap<K>
 where
     T: Into<HashMap<K, Handle<K::Asset>>>,
@@ -234,8 +142,317 @@ where
 }
 
 impl<K: AssetKey> HandleMap<K> {
-    pub fn all_loaded(&self, asset_server: &AssetServer) -> bool {
-        self.values()
-            .all(|x| asset_server.is_loaded_with_dependencies(x))
+    /// Count how many of these handles have loaded, and note any that failed.
+    pub fn track_progress(
+        &self,
+        progress: &mut LoadProgress,
+        asset_server: &AssetServer,
+        assets: &Assets<K::Asset>,
+    ) {
+        for handle in self.values() {
+            progress.track(handle, asset_server, assets);
+        }
+    }
+}
+
+/// How far along a group of assets is with loading.
+#[derive(Debug, Default)]
+pub struct LoadProgress {
+    pub loaded: usize,
+    pub total: usize,
+    /// The paths of assets that failed to load, or that depend on one that did.
+    pub failed: Vec<String>,
+}
+
+impl LoadProgress {
+    pub fn is_done(&self) -> bool {
+        self.loaded == self.total
+    }
+
+    pub fn track<A: Asset>(
+        &mut self,
+        handle: &Handle<A>,
+        asset_server: &AssetServer,
+        assets: &Assets<A>,
+    ) {
+        self.total += 1;
+        let failed = matches!(
+            asset_server.get_load_state(handle),
+            Some(LoadState::Failed { .. })
+        ) || matches!(
+            asset_server.get_recursive_dependency_load_state(handle),
+            Some(RecursiveDependencyLoadState::Failed { .. })
+        );
+        if failed {
+            let path = handle
+                .path()
+                .map(|path| path.to_string())
+                .unwrap_or_else(|| format!("{:?}", handle.id()));
+            self.failed.push(path);
+        } else if asset_server.is_loaded_with_dependencies(handle)
+            // Assets added directly, like the atlas layouts, aren't known to the asset server.
+            || (asset_server.get_load_state(handle).is_none() && assets.contains(handle))
+        {
+            self.loaded += 1;
+        }
+    }
+}
+
+/// The manifest and every handle map, for checking how far along loading is.
+#[derive(SystemParam)]
+pub struct GameAssets<'w> {
+    asset_server: Res<'w, AssetServer>,
+    manifest_handle: Res<'w, AssetManifestHandle>,
+    manifests: Res<'w, Assets<AssetManifest>>,
+    image_handles: Res<'w, HandleMap<ImageKey>>,
+    images: Res<'w, Assets<Image>>,
+    atlas_layout_handles: Res<'w, HandleMap<AtlasLayoutKey>>,
+    atlas_layouts: Res<'w, Assets<TextureAtlasLayout>>,
+    sfx_handles: Res<'w, HandleMap<SfxKey>>,
+    sfx_table_handles: Res<'w, HandleMap<SfxTableKey>>,
+    sfx_tables: Res<'w, Assets<SfxTable>>,
+    soundtrack_handles: Res<'w, HandleMap<SoundtrackKey>>,
+    audio_sources: Res<'w, Assets<AudioSource>>,
+    ldtk_handles: Res<'w, HandleMap<LdtkKey>>,
+    ldtk_projects: Res<'w, Assets<LdtkProject>>,
+}
+
+impl GameAssets<'_> {
+    pub fn progress(&self) -> LoadProgress {
+        let asset_server = &self.asset_server;
+        let mut progress = LoadProgress::default();
+        progress.track(&self.manifest_handle.handle, asset_server, &self.manifests);
+        self.image_handles
+            .track_progress(&mut progress, asset_server, &self.images);
+        self.atlas_layout_handles
+            .track_progress(&mut progress, asset_server, &self.atlas_layouts);
+        self.sfx_handles
+            .track_progress(&mut progress, asset_server, &self.audio_sources);
+        self.sfx_table_handles
+            .track_progress(&mut progress, asset_server, &self.sfx_tables);
+        self.soundtrack_handles
+            .track_progress(&mut progress, asset_server, &self.audio_sources);
+        self.ldtk_handles
+            .track_progress(&mut progress, asset_server, &self.ldtk_projects);
+        progress
+    }
+
+    /// Whether the handle maps have been filled in from the manifest yet.
+    pub fn manifest_applied(&self) -> bool {
+        self.manifest_handle.applied
+    }
+
+    /// Whether the asset at `path` has started loading again since it last finished.
+    pub fn is_loading(&self, path: &str) -> bool {
+        self.asset_server
+            .get_path_id(AssetPath::parse(path))
+            .and_then(|id| self.asset_server.get_load_state(id))
+            .is_some_and(|state| matches!(state, LoadState::Loading))
+    }
+}
+
+impl<K: AssetKey + Copy + Eq + Hash> HandleMap<K> {
+    /// Load every path listed for these keys, replacing whatever was loaded before.
+    fn load_paths(&mut self, asset_server: &AssetServer, paths: &StdHashMap<K, String>) {
+        self.0 = paths
+            .iter()
+            .map(|(key, path)| (*key, asset_server.load(path.clone())))
+            .collect();
+    }
+}
+
+/// The path of every asset the game uses, by key.
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct AssetManifest {
+    pub images: StdHashMap<ImageKey, ImageEntry>,
+    pub atlas_layouts: StdHashMap<AtlasLayoutKey, AtlasLayoutEntry>,
+    pub sfx: StdHashMap<SfxKey, String>,
+    pub sfx_tables: StdHashMap<SfxTableKey, String>,
+    pub soundtracks: StdHashMap<SoundtrackKey, String>,
+    pub ldtk: StdHashMap<LdtkKey, String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImageEntry {
+    pub path: String,
+    #[serde(default)]
+    pub sampler: SamplerSetting,
+}
+
+/// How an image is filtered when it's scaled.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub enum SamplerSetting {
+    /// Crisp pixels, for pixel art.
+    #[default]
+    Nearest,
+    Linear,
+}
+
+impl SamplerSetting {
+    fn image_sampler(self) -> ImageSampler {
+        match self {
+            SamplerSetting::Nearest => ImageSampler::nearest(),
+            SamplerSetting::Linear => ImageSampler::linear(),
+        }
+    }
+}
+
+/// A sprite sheet cut into a grid of equally sized tiles.
+#[derive(Debug, Deserialize)]
+pub struct AtlasLayoutEntry {
+    pub tile_size: (u32, u32),
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl AtlasLayoutEntry {
+    fn layout(&self) -> TextureAtlasLayout {
+        let (width, height) = self.tile_size;
+        TextureAtlasLayout::from_grid(
+            UVec2::new(width, height),
+            self.columns,
+            self.rows,
+            None,
+            None,
+        )
+    }
+}
+
+/// The manifest in use, and whether the handle maps have been filled in from it yet.
+#[derive(Resource, Debug)]
+pub struct AssetManifestHandle {
+    pub handle: Handle<AssetManifest>,
+    pub applied: bool,
+}
+
+impl FromWorld for AssetManifestHandle {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        Self {
+            handle: asset_server.load("game.manifest.ron"),
+            applied: false,
+        }
+    }
+}
+
+fn apply_asset_manifest(
+    mut asset_events: EventReader<AssetEvent<AssetManifest>>,
+    mut manifest_handle: ResMut<AssetManifestHandle>,
+    manifests: Res<Assets<AssetManifest>>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut image_handles: ResMut<HandleMap<ImageKey>>,
+    mut atlas_layout_handles: ResMut<HandleMap<AtlasLayoutKey>>,
+    mut sfx_handles: ResMut<HandleMap<SfxKey>>,
+    mut sfx_table_handles: ResMut<HandleMap<SfxTableKey>>,
+    mut soundtrack_handles: ResMut<HandleMap<SoundtrackKey>>,
+    mut ldtk_handles: ResMut<HandleMap<LdtkKey>>,
+) {
+    let changed = asset_events.read().any(|event| match event {
+        AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => {
+            *id == manifest_handle.handle.id()
+        }
+        _ => false,
+    });
+    if !changed {
+        return;
+    }
+    let Some(manifest) = manifests.get(&manifest_handle.handle) else {
+        return;
+    };
+
+    image_handles.0 = manifest
+        .images
+        .iter()
+        .map(|(key, entry)| {
+            let sampler = entry.sampler;
+            let handle = asset_server.load_with_settings(
+                entry.path.clone(),
+                move |settings: &mut ImageLoaderSettings| {
+                    settings.sampler = sampler.image_sampler();
+                },
+            );
+            (*key, handle)
+        })
+        .collect();
+
+    // Update existing layouts in place so sprites that already use them pick up the change.
+    let mut layouts = HashMap::default();
+    for (key, entry) in &manifest.atlas_layouts {
+        let handle = match atlas_layout_handles.get(key) {
+            Some(handle) => {
+                texture_atlas_layouts.insert(handle, entry.layout());
+                handle.clone()
+            }
+            None => texture_atlas_layouts.add(entry.layout()),
+        };
+        layouts.insert(*key, handle);
+    }
+    atlas_layout_handles.0 = layouts;
+
+    sfx_handles.load_paths(&asset_server, &manifest.sfx);
+    sfx_table_handles.load_paths(&asset_server, &manifest.sfx_tables);
+    soundtrack_handles.load_paths(&asset_server, &manifest.soundtracks);
+    ldtk_handles.load_paths(&asset_server, &manifest.ldtk);
+
+    if manifest_handle.applied {
+        info!("Reloaded the asset manifest");
+    }
+    manifest_handle.applied = true;
+}
+
+/// Loads any asset that can be deserialized from a RON file with one of the given extensions.
+pub struct RonAssetLoader<A> {
+    extensions: &'static [&'static str],
+    _asset: PhantomData<fn() -> A>,
+}
+
+impl<A> RonAssetLoader<A> {
+    pub fn new(extensions: &'static [&'static str]) -> Self {
+        Self {
+            extensions,
+            _asset: PhantomData,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RonAssetLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl std::fmt::Display for RonAssetLoaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RonAssetLoaderError::Io(e) => write!(f, "could not read file: {e}"),
+            RonAssetLoaderError::Ron(e) => write!(f, "could not parse file: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for RonAssetLoaderError {}
+
+impl<A: Asset + DeserializeOwned> AssetLoader for RonAssetLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = RonAssetLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<A, RonAssetLoaderError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(RonAssetLoaderError::Io)?;
+        ron::de::from_bytes(&bytes).map_err(RonAssetLoaderError::Ron)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
     }
 }
@@ -1,27 +1,17 @@
-use crate::game::audio::soundtrack::MusicPlayer;
+use crate::game::audio::{AudioChannel, Gain};
 use crate::game::settings::Settings;
-use bevy::audio::Volume;
 use bevy::prelude::*;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(Update, (adjust_volume, enable_or_disable));
+    app.add_systems(Update, adjust_volume);
 }
 
-fn adjust_volume(settings: Res<Settings>, mut playback_settings: Query<&mut AudioSink>) {
-    for mut sink in playback_settings.iter_mut() {
-        sink.set_volume(settings.volume);
-    }
-}
-
-fn enable_or_disable(
+fn adjust_volume(
     settings: Res<Settings>,
-    mut playback_settings: Query<&mut AudioSink, With<MusicPlayer>>,
+    playback_settings: Query<(&AudioSink, &AudioChannel, Option<&Gain>)>,
 ) {
-    for mut sink in playback_settings.iter_mut() {
-        if sink.is_paused() && settings.music {
-            sink.play();
-        } else if !sink.is_paused() && !settings.music {
-            sink.pause();
-        }
+    for (sink, channel, gain) in &playback_settings {
+        let gain = gain.map_or(1.0, |gain| gain.0);
+        sink.set_volume(settings.volume(*channel) * gain);
     }
 }
@@ -5,5 +5,41 @@ pub mod soundtrack;
 use bevy::prelude::*;
 
 pub fn plugin(app: &mut App) {
+    app.register_type::<(AudioChannel, Gain)>();
     app.add_plugins((sfx::plugin, soundtrack::plugin, manager::plugin));
 }
+
+/// Which volume setting controls a sound. Everything is also scaled by [`AudioChannel::Master`].
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Component)]
+pub enum AudioChannel {
+    Master,
+    Music,
+    Sfx,
+    Ui,
+}
+
+/// Scales the volume of a single sound on top of its [`AudioChannel`].
+#[derive(Component, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Gain(pub f32);
+
+impl AudioChannel {
+    pub const ALL: [AudioChannel; 4] = [
+        AudioChannel::Master,
+        AudioChannel::Music,
+        AudioChannel::Sfx,
+        AudioChannel::Ui,
+    ];
+}
+
+impl std::fmt::Display for AudioChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AudioChannel::Master => write!(f, "Master"),
+            AudioChannel::Music => write!(f, "Music"),
+            AudioChannel::Sfx => write!(f, "Effects"),
+            AudioChannel::Ui => write!(f, "Interface"),
+        }
+    }
+}
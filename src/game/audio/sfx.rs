@@ -1,28 +1,121 @@
-use bevy::{audio::PlaybackMode, prelude::*};
-use rand::seq::SliceRandom;
+use bevy::{
+    audio::{PlaybackMode, Volume},
+    prelude::*,
+};
+use rand::{seq::SliceRandom, Rng};
+use rand_chacha::ChaCha8Rng;
+use serde::Deserialize;
+use std::collections::HashMap;
 
-use crate::game::assets::{HandleMap, SfxKey};
+use crate::game::{
+    assets::{HandleMap, RonAssetLoader, SfxKey, SfxTableKey},
+    audio::{AudioChannel, Gain},
+    cargo::{CargoTransferred, TransferDirection},
+    contracts::ContractCompleted,
+    movement::{CycleFinished, ShipBlocked, ShipMoved, ShipRotated},
+    rng::{GameRng, RngStream},
+    settings::Settings,
+};
 
 pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<SfxTable>();
+    app.register_asset_loader(RonAssetLoader::<SfxTable>::new(&["sfx.ron"]));
     app.observe(play_sfx);
+
+    app.observe(play_ship_moved);
+    app.observe(play_ship_rotated);
+    app.observe(play_ship_blocked);
+    app.observe(play_cargo_transferred);
+    app.observe(play_contract_completed);
+    app.add_systems(
+        Update,
+        play_cycle_finished.run_if(on_event::<CycleFinished>()),
+    );
 }
 
 fn play_sfx(
     trigger: Trigger<PlaySfx>,
     mut commands: Commands,
     sfx_handles: Res<HandleMap<SfxKey>>,
+    sfx_table_handles: Res<HandleMap<SfxTableKey>>,
+    sfx_tables: Res<Assets<SfxTable>>,
+    settings: Res<Settings>,
+    mut game_rng: ResMut<GameRng>,
 ) {
-    let sfx_key = match trigger.event() {
-        PlaySfx::Key(key) => *key,
-        PlaySfx::RandomStep => random_step(),
+    let rng = game_rng.stream(RngStream::Audio);
+    let (sfx_key, gain, speed) = match trigger.event() {
+        PlaySfx::Key(key) => (*key, 1.0, 1.0),
+        PlaySfx::RandomStep => (random_step(rng), 1.0, 1.0),
+        PlaySfx::Game(game_sfx) => {
+            let Some(sound) = sfx_table_handles
+                .get(&SfxTableKey::Gameplay)
+                .and_then(|handle| sfx_tables.get(handle))
+                .and_then(|table| table.sounds.get(game_sfx))
+            else {
+                return;
+            };
+            let Some(key) = sound.keys.choose(rng) else {
+                return;
+            };
+            (*key, vary(rng, sound.volume), vary(rng, sound.pitch))
+        }
+    };
+    // The manifest may not have loaded yet, or may have left this sound out.
+    let Some(source) = sfx_handles.get(&sfx_key) else {
+        return;
     };
-    commands.spawn(AudioSourceBundle {
-        source: sfx_handles[&sfx_key].clone_weak(),
-        settings: PlaybackSettings {
-            mode: PlaybackMode::Despawn,
-            ..default()
+    let channel = sfx_channel(sfx_key);
+    commands.spawn((
+        AudioSourceBundle {
+            source: source.clone_weak(),
+            settings: PlaybackSettings {
+                mode: PlaybackMode::Despawn,
+                volume: Volume::new(settings.volume(channel) * gain),
+                speed,
+                ..default()
+            },
         },
-    });
+        channel,
+        Gain(gain),
+    ));
+}
+
+/// Interface sounds get their own channel so they can be turned down separately from the game.
+fn sfx_channel(sfx_key: SfxKey) -> AudioChannel {
+    match sfx_key {
+        SfxKey::ButtonHover | SfxKey::ButtonPress => AudioChannel::Ui,
+        _ => AudioChannel::Sfx,
+    }
+}
+
+fn play_ship_moved(_trigger: Trigger<ShipMoved>, mut commands: Commands) {
+    commands.trigger(PlaySfx::Game(GameSfx::ShipMoved));
+}
+
+fn play_ship_rotated(_trigger: Trigger<ShipRotated>, mut commands: Commands) {
+    commands.trigger(PlaySfx::Game(GameSfx::ShipRotated));
+}
+
+fn play_ship_blocked(trigger: Trigger<ShipBlocked>, mut commands: Commands) {
+    let game_sfx = match trigger.event().other_ship {
+        Some(_) => GameSfx::BumpedShip,
+        None => GameSfx::BumpedWall,
+    };
+    commands.trigger(PlaySfx::Game(game_sfx));
+}
+
+fn play_cargo_transferred(trigger: Trigger<CargoTransferred>, mut commands: Commands) {
+    if trigger.event().direction == TransferDirection::Load {
+        commands.trigger(PlaySfx::Game(GameSfx::CraneLoaded));
+    }
+}
+
+fn play_contract_completed(_trigger: Trigger<ContractCompleted>, mut commands: Commands) {
+    commands.trigger(PlaySfx::Game(GameSfx::ContractCompleted));
+}
+
+fn play_cycle_finished(mut commands: Commands) {
+    commands.trigger(PlaySfx::Game(GameSfx::CycleFinished));
 }
 
 /// Trigger this event to play a single sound effect.
@@ -30,11 +123,57 @@ fn play_sfx(
 pub enum PlaySfx {
     Key(SfxKey),
     RandomStep,
+    /// Play whichever sound the gameplay [`SfxTable`] lists for this moment.
+    Game(GameSfx),
 }
 
-fn random_step() -> SfxKey {
+fn random_step(rng: &mut ChaCha8Rng) -> SfxKey {
     [SfxKey::Step1, SfxKey::Step2, SfxKey::Step3, SfxKey::Step4]
-        .choose(&mut rand::thread_rng())
+        .choose(rng)
         .copied()
         .unwrap()
 }
+
+/// Pick a value from an inclusive `(min, max)` range.
+fn vary(rng: &mut ChaCha8Rng, (min, max): (f32, f32)) -> f32 {
+    if max > min {
+        rng.gen_range(min..=max)
+    } else {
+        min
+    }
+}
+
+/// Moments in the game that have a sound effect.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Deserialize)]
+pub enum GameSfx {
+    ShipMoved,
+    ShipRotated,
+    BumpedWall,
+    BumpedShip,
+    CraneLoaded,
+    CycleFinished,
+    ContractCompleted,
+}
+
+/// Which sounds play for each [`GameSfx`], loaded from a `.sfx.ron` file
+/// so they can be tuned without recompiling.
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct SfxTable {
+    pub sounds: HashMap<GameSfx, SfxVariation>,
+}
+
+/// A set of sounds to pick from at random, played with a random volume and pitch.
+#[derive(Debug, Deserialize)]
+pub struct SfxVariation {
+    pub keys: Vec<SfxKey>,
+    /// The range to pick a volume multiplier from.
+    #[serde(default = "no_variation")]
+    pub volume: (f32, f32),
+    /// The range to pick a playback speed from, which also shifts the pitch.
+    #[serde(default = "no_variation")]
+    pub pitch: (f32, f32),
+}
+
+fn no_variation() -> (f32, f32) {
+    (1.0, 1.0)
+}
@@ -1,52 +1,259 @@
-use bevy::{audio::PlaybackMode, prelude::*};
+use bevy::{
+    audio::{PlaybackMode, Volume},
+    prelude::*,
+    utils::HashMap,
+};
+use rand::seq::SliceRandom;
+use rand_chacha::ChaCha8Rng;
 
-use crate::game::assets::{HandleMap, SoundtrackKey};
+use crate::game::{
+    assets::{HandleMap, SoundtrackKey},
+    audio::{AudioChannel, Gain},
+    rng::{GameRng, RngStream},
+    settings::Settings,
+};
+
+/// How long it takes one soundtrack to fade into another, in seconds.
+const CROSSFADE_SECONDS: f32 = 1.5;
 
 pub(super) fn plugin(app: &mut App) {
-    app.register_type::<IsSoundtrack>();
+    app.register_type::<Soundtrack>();
+    app.register_type::<Crossfade>();
+    app.init_resource::<SoundtrackPlayer>();
     app.observe(play_soundtrack);
+    app.add_systems(Update, (advance_playlist, update_crossfades).chain());
 }
 
 #[derive(Component, Default, Debug, Reflect)]
 pub struct MusicPlayer;
 
+/// A set of soundtracks that play one after another while a screen is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Playlist {
+    /// Identifies the playlist, so it resumes where it left off when it's played again.
+    pub name: &'static str,
+    pub tracks: &'static [SoundtrackKey],
+    /// Play the tracks in a random order, reshuffled every time the playlist wraps around.
+    pub shuffle: bool,
+}
+
+// Each screen only has one track so far. Add more to these as they're written.
+impl Playlist {
+    pub const MENUS: Playlist = Playlist {
+        name: "menus",
+        tracks: &[SoundtrackKey::Menus],
+        shuffle: false,
+    };
+
+    pub const GAMEPLAY: Playlist = Playlist {
+        name: "gameplay",
+        tracks: &[SoundtrackKey::Gameplay],
+        shuffle: false,
+    };
+}
+
+/// Where each playlist is up to, and which one is playing.
+#[derive(Resource, Debug, Default)]
+struct SoundtrackPlayer {
+    active: Option<&'static str>,
+    positions: HashMap<&'static str, PlaylistPosition>,
+}
+
+impl SoundtrackPlayer {
+    fn active_position(&mut self) -> Option<&mut PlaylistPosition> {
+        self.active.and_then(|name| self.positions.get_mut(name))
+    }
+}
+
+#[derive(Debug)]
+struct PlaylistPosition {
+    playlist: Playlist,
+    order: Vec<SoundtrackKey>,
+    index: usize,
+}
+
+impl PlaylistPosition {
+    fn new(playlist: Playlist, rng: &mut ChaCha8Rng) -> Self {
+        let mut position = Self {
+            playlist,
+            order: playlist.tracks.to_vec(),
+            index: 0,
+        };
+        position.shuffle(rng);
+        position
+    }
+
+    fn shuffle(&mut self, rng: &mut ChaCha8Rng) {
+        if self.playlist.shuffle {
+            self.order.shuffle(rng);
+        }
+    }
+
+    fn current(&self) -> Option<SoundtrackKey> {
+        self.order.get(self.index).copied()
+    }
+
+    fn advance(&mut self, rng: &mut ChaCha8Rng) {
+        self.index += 1;
+        if self.index >= self.order.len() {
+            self.index = 0;
+            self.shuffle(rng);
+        }
+    }
+}
+
 fn play_soundtrack(
     trigger: Trigger<PlaySoundtrack>,
     mut commands: Commands,
     soundtrack_handles: Res<HandleMap<SoundtrackKey>>,
-    soundtrack_query: Query<Entity, With<IsSoundtrack>>,
+    mut player: ResMut<SoundtrackPlayer>,
+    mut track_query: Query<(&Soundtrack, &mut Crossfade)>,
+    mut game_rng: ResMut<GameRng>,
 ) {
-    for entity in &soundtrack_query {
-        commands.entity(entity).despawn_recursive();
-    }
+    let key = match trigger.event() {
+        PlaySoundtrack::Playlist(playlist) => {
+            if player.active == Some(playlist.name) {
+                return;
+            }
+            player.active = Some(playlist.name);
+            player
+                .positions
+                .entry(playlist.name)
+                .or_insert_with(|| {
+                    PlaylistPosition::new(*playlist, game_rng.stream(RngStream::Audio))
+                })
+                .current()
+        }
+        PlaySoundtrack::Disable => {
+            player.active = None;
+            None
+        }
+    };
+    crossfade_to(&mut commands, &soundtrack_handles, &mut track_query, key);
+}
 
-    let soundtrack_key = match trigger.event() {
-        PlaySoundtrack::Key(key) => *key,
-        PlaySoundtrack::Disable => return,
+/// Fade every soundtrack out except `key`, which fades in from wherever it was paused.
+fn crossfade_to(
+    commands: &mut Commands,
+    soundtrack_handles: &HandleMap<SoundtrackKey>,
+    track_query: &mut Query<(&Soundtrack, &mut Crossfade)>,
+    key: Option<SoundtrackKey>,
+) {
+    let mut found = false;
+    for (track, mut crossfade) in track_query {
+        if Some(track.0) == key {
+            crossfade.target = 1.0;
+            found = true;
+        } else {
+            crossfade.target = 0.0;
+        }
+    }
+    let Some(key) = key else {
+        return;
     };
-    commands.spawn((
-        AudioSourceBundle {
-            source: soundtrack_handles[&soundtrack_key].clone_weak(),
-            settings: PlaybackSettings {
-                mode: PlaybackMode::Loop,
-                ..default()
+    if !found {
+        let Some(handle) = soundtrack_handles.get(&key) else {
+            warn!("The asset manifest has no soundtrack for {key:?}");
+            return;
+        };
+        commands.spawn((
+            AudioSourceBundle {
+                source: handle.clone_weak(),
+                settings: PlaybackSettings {
+                    mode: PlaybackMode::Once,
+                    volume: Volume::new(0.0),
+                    ..default()
+                },
             },
-        },
-        MusicPlayer,
-        IsSoundtrack,
-    ));
+            AudioChannel::Music,
+            MusicPlayer,
+            Soundtrack(key),
+            Gain(0.0),
+            Crossfade { target: 1.0 },
+        ));
+    }
+}
+
+/// Move on to the next track in the playlist when the current one ends.
+fn advance_playlist(
+    mut commands: Commands,
+    soundtrack_handles: Res<HandleMap<SoundtrackKey>>,
+    mut player: ResMut<SoundtrackPlayer>,
+    finished_query: Query<(Entity, &Soundtrack, &AudioSink)>,
+    mut track_query: Query<(&Soundtrack, &mut Crossfade)>,
+    mut gain_query: Query<&mut Gain>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    for (entity, track, sink) in &finished_query {
+        if !sink.empty() {
+            continue;
+        }
+        // Removing the sink makes bevy queue the track again from the start.
+        // Queue it paused, so it stays silent until it's faded back in.
+        commands
+            .entity(entity)
+            .remove::<AudioSink>()
+            .insert(PlaybackSettings::ONCE.with_volume(Volume::ZERO).paused());
+        if let Ok(mut gain) = gain_query.get_mut(entity) {
+            gain.0 = 0.0;
+        }
+
+        let Some(position) = player
+            .active_position()
+            .filter(|position| position.current() == Some(track.0))
+        else {
+            continue;
+        };
+        position.advance(game_rng.stream(RngStream::Audio));
+        let next = position.current();
+        crossfade_to(&mut commands, &soundtrack_handles, &mut track_query, next);
+    }
+}
+
+fn update_crossfades(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut track_query: Query<(&Crossfade, &mut Gain, Option<&AudioSink>)>,
+) {
+    let step = time.delta_seconds() / CROSSFADE_SECONDS;
+    let music_on = settings.volume(AudioChannel::Music) > 0.0;
+    for (crossfade, mut gain, sink) in &mut track_query {
+        if gain.0 < crossfade.target {
+            gain.0 = (gain.0 + step).min(crossfade.target);
+        } else if gain.0 > crossfade.target {
+            gain.0 = (gain.0 - step).max(crossfade.target);
+        }
+
+        // Pause silent tracks rather than stopping them, so they resume where they left off.
+        let Some(sink) = sink else {
+            continue;
+        };
+        let audible = music_on && gain.0 > 0.0;
+        if sink.is_paused() && audible {
+            sink.play();
+        } else if !sink.is_paused() && !audible {
+            sink.pause();
+        }
+    }
 }
 
 /// Trigger this event to play or disable the soundtrack.
-/// Playing a new soundtrack will overwrite the previous one.
-/// Soundtracks will loop.
+/// Playing a new playlist crossfades from the previous one,
+/// picking up from where that playlist was last stopped.
 #[derive(Event)]
 pub enum PlaySoundtrack {
-    Key(SoundtrackKey),
+    Playlist(Playlist),
     Disable,
 }
 
-/// Marker component for the soundtrack entity so we can find it later.
+/// The soundtrack an entity is playing, so we can find it later.
 #[derive(Component, Reflect)]
 #[reflect(Component)]
-struct IsSoundtrack;
+struct Soundtrack(SoundtrackKey);
+
+/// The [`Gain`] a soundtrack is fading towards.
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+struct Crossfade {
+    target: f32,
+}
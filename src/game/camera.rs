@@ -1,10 +1,18 @@
 use crate::game::controls::CameraAction;
+use crate::game::game_ui::SelectedShip;
 use crate::game::spawn::player::Player;
 use bevy::prelude::*;
 use leafwing_input_manager::action_state::ActionState;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(Update, (zoom_camera, camera_follow));
+    app.add_systems(
+        Update,
+        (
+            zoom_camera,
+            follow_selected_ship.run_if(resource_changed::<SelectedShip>),
+            camera_follow,
+        ),
+    );
 }
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Default, Reflect)]
@@ -40,3 +48,19 @@ fn camera_follow(
         }
     }
 }
+
+fn follow_selected_ship(
+    mut commands: Commands,
+    selected_ship: Res<SelectedShip>,
+    ship_query: Query<Entity, With<Player>>,
+) {
+    for entity in &ship_query {
+        if selected_ship.0 == Some(entity) {
+            commands
+                .entity(entity)
+                .insert(CameraFollow { threshold: 120.0 });
+        } else {
+            commands.entity(entity).remove::<CameraFollow>();
+        }
+    }
+}
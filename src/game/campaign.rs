@@ -0,0 +1,164 @@
+//! The campaign: every level in the LDtk project, played in order.
+//! A level is unlocked once the one before it has been completed.
+//! Progress is stored in the data directory alongside the saves, so it carries over between runs.
+
+use crate::game::assets::{HandleMap, LdtkKey};
+use crate::game::objectives::LevelResult;
+use crate::game::spawn::harbour::PracticeHarbour;
+use crate::screen::Screen;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_ecs_ldtk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(Campaign::load());
+    app.register_type::<Campaign>();
+    app.insert_resource(LevelSelection::index(0));
+    app.add_systems(
+        OnEnter(Screen::Results),
+        record_level_result.run_if(not(resource_exists::<PracticeHarbour>)),
+    );
+    app.add_systems(
+        Update,
+        save_campaign
+            .run_if(resource_changed::<Campaign>.and_then(not(resource_added::<Campaign>))),
+    );
+}
+
+/// The player's progress through the campaign.
+#[derive(Resource, Debug, Clone, Default, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct Campaign {
+    /// The best star rating for each completed level, by level iid.
+    pub completed: HashMap<String, u32>,
+}
+
+impl Campaign {
+    pub fn is_completed(&self, level: &CampaignLevel) -> bool {
+        self.completed.contains_key(&level.iid)
+    }
+
+    pub fn best_stars(&self, level: &CampaignLevel) -> Option<u32> {
+        self.completed.get(&level.iid).copied()
+    }
+
+    pub fn is_unlocked(&self, levels: &[CampaignLevel], index: usize) -> bool {
+        index == 0
+            || levels
+                .get(index - 1)
+                .is_some_and(|previous| self.is_completed(previous))
+    }
+
+    /// The first unlocked level that has not been completed yet,
+    /// or the last level once they all have been.
+    pub fn next_level(&self, levels: &[CampaignLevel]) -> usize {
+        levels
+            .iter()
+            .position(|level| !self.is_completed(level))
+            .unwrap_or(levels.len().saturating_sub(1))
+    }
+}
+
+/// A level in the campaign, in the order it appears in the LDtk project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CampaignLevel {
+    pub iid: String,
+    pub identifier: String,
+}
+
+/// Every level in the main LDtk project, or none if it hasn't loaded.
+pub fn campaign_levels(
+    ldtk_handles: &HandleMap<LdtkKey>,
+    ldtk_projects: &Assets<LdtkProject>,
+) -> Vec<CampaignLevel> {
+    let Some(handle) = ldtk_handles.get(&LdtkKey::Main) else {
+        warn!("The asset manifest has no main LDtk project");
+        return Vec::new();
+    };
+    ldtk_projects
+        .get(handle)
+        .map(|project| {
+            project
+                .iter_raw_levels()
+                .map(|level| CampaignLevel {
+                    iid: level.iid.clone(),
+                    identifier: level.identifier.clone(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The index of the selected level in the campaign.
+pub fn current_level_index(level_selection: &LevelSelection) -> usize {
+    match level_selection {
+        LevelSelection::Indices(indices) => indices.level,
+        _ => 0,
+    }
+}
+
+fn record_level_result(
+    level_result: Res<LevelResult>,
+    level_selection: Res<LevelSelection>,
+    ldtk_handles: Res<HandleMap<LdtkKey>>,
+    ldtk_projects: Res<Assets<LdtkProject>>,
+    mut campaign: ResMut<Campaign>,
+) {
+    if !level_result.won {
+        return;
+    }
+    let levels = campaign_levels(&ldtk_handles, &ldtk_projects);
+    let Some(level) = levels.get(current_level_index(&level_selection)) else {
+        return;
+    };
+    let best = campaign.completed.entry(level.iid.clone()).or_default();
+    *best = (*best).max(level_result.stars);
+}
+
+fn save_campaign(campaign: Res<Campaign>) {
+    if let Err(e) = campaign.save() {
+        warn!("Failed to save campaign progress: {e}");
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Campaign {
+    fn path() -> Option<std::path::PathBuf> {
+        crate::game::save::data_dir().map(|dir| dir.join("campaign.ron"))
+    }
+
+    /// Read the campaign file, starting afresh if it is missing or unreadable.
+    pub fn load() -> Self {
+        let Some(contents) = Self::path().and_then(|path| std::fs::read_to_string(path).ok())
+        else {
+            return Self::default();
+        };
+        ron::from_str(&contents).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable campaign file: {e}");
+            Self::default()
+        })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("no data directory")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())?;
+        std::fs::write(path, contents).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(target_family = "wasm")]
+impl Campaign {
+    pub fn load() -> Self {
+        Self::default()
+    }
+
+    fn save(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+//! Cargo carried by ships and stocked at docks.
+
+use crate::game::spawn::player::Player;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::ldtk_fields::LdtkFields;
+use bevy_ecs_ldtk::prelude::EntityInstance;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(CargoType, Hold, DockStock)>();
+    app.add_systems(Update, update_hold_sprite);
+}
+
+/// Number of frames in the ship sprite sheet beyond the empty one.
+const HOLD_SPRITE_FRAMES: u32 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum CargoType {
+    Grain,
+    Coal,
+    Containers,
+}
+
+impl CargoType {
+    pub const ALL: [CargoType; 3] = [CargoType::Grain, CargoType::Coal, CargoType::Containers];
+}
+
+impl FromStr for CargoType {
+    type Err = String;
+
+    /// Parse the identifiers used by the `Cargo` enum in LDtk.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Grain" => Ok(Self::Grain),
+            "Coal" => Ok(Self::Coal),
+            "Containers" => Ok(Self::Containers),
+            _ => Err(format!("Unknown cargo type: {s}")),
+        }
+    }
+}
+
+impl std::fmt::Display for CargoType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CargoType::Grain => write!(f, "Grain"),
+            CargoType::Coal => write!(f, "Coal"),
+            CargoType::Containers => write!(f, "Containers"),
+        }
+    }
+}
+
+/// A ship's cargo hold. Holds only carry one type of cargo at a time.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct Hold {
+    pub cargo: Option<CargoType>,
+    pub amount: u32,
+    pub capacity: u32,
+}
+
+impl Default for Hold {
+    fn default() -> Self {
+        Self {
+            cargo: None,
+            amount: 0,
+            capacity: 8,
+        }
+    }
+}
+
+impl Hold {
+    pub fn space(&self) -> u32 {
+        self.capacity.saturating_sub(self.amount)
+    }
+
+    pub fn can_take(&self, cargo: CargoType) -> bool {
+        self.space() > 0 && self.cargo.map_or(true, |held| held == cargo)
+    }
+
+    /// Which frame of the ship sprite sheet shows how full the hold is.
+    pub fn sprite_index(&self) -> usize {
+        if self.capacity == 0 {
+            return 0;
+        }
+        let frame = (self.amount * HOLD_SPRITE_FRAMES + self.capacity / 2) / self.capacity;
+        frame.min(HOLD_SPRITE_FRAMES) as usize
+    }
+}
+
+/// The cargo waiting at a dock.
+/// Docks with no cargo type will accept any cargo, and then only more of that type.
+#[derive(
+    Component, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize,
+)]
+#[reflect(Component)]
+pub struct DockStock {
+    pub cargo: Option<CargoType>,
+    pub amount: u32,
+}
+
+impl DockStock {
+    pub fn accepts(&self, cargo: CargoType) -> bool {
+        self.cargo.map_or(true, |stocked| stocked == cargo)
+    }
+}
+
+impl From<&EntityInstance> for DockStock {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        let cargo = entity_instance
+            .get_maybe_enum_field("Cargo")
+            .ok()
+            .and_then(|cargo| cargo.as_deref())
+            .and_then(|cargo| cargo.parse().ok());
+        let amount = entity_instance
+            .get_int_field("Stock")
+            .map_or(0, |stock| (*stock).max(0) as u32);
+        Self { cargo, amount }
+    }
+}
+
+fn update_hold_sprite(
+    mut ship_query: Query<(&Hold, &mut TextureAtlas), (With<Player>, Changed<Hold>)>,
+) {
+    for (hold, mut atlas) in &mut ship_query {
+        atlas.index = hold.sprite_index();
+    }
+}
+
+/// Whether cargo went onto or off a ship.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransferDirection {
+    Load,
+    Unload,
+}
+
+/// Triggered whenever cargo moves between a ship and a dock.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct CargoTransferred {
+    pub ship: Entity,
+    pub dock: Entity,
+    pub cargo: CargoType,
+    pub amount: u32,
+    pub direction: TransferDirection,
+}
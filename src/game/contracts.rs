@@ -0,0 +1,321 @@
+//! Delivery contracts: move cargo from one dock to another before a deadline.
+//!
+//! Contracts are read from the level's `Contracts` field, which holds lines like
+//! `4 Grain from Granary to Warehouse within 3`. Levels without any get a few generated
+//! from the docks' stock instead.
+
+use crate::game::cargo::{CargoTransferred, CargoType, DockStock, Hold, TransferDirection};
+use crate::game::game_ui::CycleNum;
+use crate::game::spawn::dock::{Dock, DockName};
+use crate::screen::Screen;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::ldtk_fields::LdtkFields;
+use bevy_ecs_ldtk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Contracts>();
+    app.register_type::<(Contracts, CargoOrigin)>();
+    app.add_systems(Update, load_contracts);
+    // Deliveries are counted when the step's commands are applied, so check the
+    // deadlines afterwards to let a delivery on the last turn count.
+    app.add_systems(
+        PostUpdate,
+        check_contracts.run_if(in_state(Screen::Playing)),
+    );
+    app.observe(track_deliveries);
+}
+
+/// How much cargo a generated contract asks for, at most.
+const GENERATED_CONTRACT_AMOUNT: u32 = 4;
+/// How many cycles a generated contract allows.
+const GENERATED_CONTRACT_CYCLES: usize = 3;
+
+/// A contract as written in LDtk, before the dock names have been looked up.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContractSpec {
+    pub amount: u32,
+    pub cargo: CargoType,
+    pub from: String,
+    pub to: String,
+    pub cycles: usize,
+}
+
+impl FromStr for ContractSpec {
+    type Err = String;
+
+    /// Parse `<amount> <cargo> from <dock> to <dock> within <cycles>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || format!("Malformed contract: {s}");
+        let (amount, rest) = s.trim().split_once(' ').ok_or_else(malformed)?;
+        let (cargo, rest) = rest.split_once(' ').ok_or_else(malformed)?;
+        let rest = rest.strip_prefix("from ").ok_or_else(malformed)?;
+        let (docks, cycles) = rest.rsplit_once(" within ").ok_or_else(malformed)?;
+        let (from, to) = docks.split_once(" to ").ok_or_else(malformed)?;
+        Ok(Self {
+            amount: amount.parse().map_err(|_| malformed())?,
+            cargo: cargo.parse()?,
+            from: from.to_string(),
+            to: to.to_string(),
+            cycles: cycles.parse().map_err(|_| malformed())?,
+        })
+    }
+}
+
+/// A generated contract, with its docks given by their index in the list of stocks it was
+/// generated from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GeneratedContract {
+    pub cargo: CargoType,
+    pub amount: u32,
+    pub from: usize,
+    pub to: usize,
+    pub cycles: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum ContractStatus {
+    Active,
+    Completed,
+    Failed,
+    Expired,
+}
+
+#[derive(Clone, Debug, Reflect)]
+pub struct Contract {
+    pub cargo: CargoType,
+    pub amount: u32,
+    pub delivered: u32,
+    pub from: Entity,
+    pub to: Entity,
+    /// The cycle at which the contract expires if it is still active.
+    pub deadline: usize,
+    pub status: ContractStatus,
+}
+
+impl Contract {
+    pub fn remaining(&self) -> u32 {
+        self.amount.saturating_sub(self.delivered)
+    }
+}
+
+/// The contracts for the current level, in the order they are shown.
+#[derive(Resource, Debug, Clone, Default, Reflect)]
+#[reflect(Resource)]
+pub struct Contracts(pub Vec<Contract>);
+
+/// Triggered when the last of a contract's cargo is delivered.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ContractCompleted {
+    pub contract: usize,
+}
+
+/// Triggered when there is no longer enough cargo left to fulfil a contract,
+/// or the destination will no longer take it.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ContractFailed {
+    pub contract: usize,
+}
+
+/// Triggered when a contract's deadline passes before it is completed.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ContractExpired {
+    pub contract: usize,
+}
+
+/// The dock a ship's cargo was loaded from, if it all came from the same one.
+/// Only cargo with a known origin counts towards a contract.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct CargoOrigin(pub Option<Entity>);
+
+fn load_contracts(
+    mut contracts: ResMut<Contracts>,
+    mut level_events: EventReader<LevelEvent>,
+    dock_query: Query<(Entity, &EntityIid, &DockName, &DockStock), With<Dock>>,
+    cycle_num: Res<CycleNum>,
+    ldtk_project_entities: Query<&Handle<LdtkProject>>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
+) {
+    for level_event in level_events.read() {
+        if let LevelEvent::Spawned(level_iid) = level_event {
+            let ldtk_project = ldtk_project_assets
+                .get(ldtk_project_entities.single())
+                .expect("LdtkProject should be loaded when level is spawned");
+            let level = ldtk_project
+                .get_raw_level_by_iid(level_iid.get())
+                .expect("spawned level should exist in project");
+
+            let mut docks: Vec<_> = dock_query.iter().collect();
+            docks.sort_by_key(|(_, iid, ..)| iid.as_str());
+            let find_dock = |name: &str| {
+                docks
+                    .iter()
+                    .find(|(_, _, dock_name, _)| dock_name.0 == name)
+                    .map(|(entity, ..)| *entity)
+            };
+
+            let mut level_contracts = Vec::new();
+            let specs = level
+                .get_maybe_strings_field("Contracts")
+                .unwrap_or_default();
+            for spec in specs.iter().flatten() {
+                let spec: ContractSpec = match spec.parse() {
+                    Ok(spec) => spec,
+                    Err(e) => {
+                        warn!("Skipping contract: {e}");
+                        continue;
+                    }
+                };
+                let (Some(from), Some(to)) = (find_dock(&spec.from), find_dock(&spec.to)) else {
+                    warn!("Skipping contract with unknown dock: {spec:?}");
+                    continue;
+                };
+                level_contracts.push(Contract {
+                    cargo: spec.cargo,
+                    amount: spec.amount,
+                    delivered: 0,
+                    from,
+                    to,
+                    deadline: cycle_num.cycle() + spec.cycles,
+                    status: ContractStatus::Active,
+                });
+            }
+
+            if level_contracts.is_empty() {
+                let stocks: Vec<_> = docks.iter().map(|(.., stock)| **stock).collect();
+                level_contracts = generate_contracts(&stocks)
+                    .into_iter()
+                    .map(|generated| Contract {
+                        cargo: generated.cargo,
+                        amount: generated.amount,
+                        delivered: 0,
+                        from: docks[generated.from].0,
+                        to: docks[generated.to].0,
+                        deadline: cycle_num.cycle() + generated.cycles,
+                        status: ContractStatus::Active,
+                    })
+                    .collect();
+            }
+            *contracts = Contracts(level_contracts);
+        }
+    }
+}
+
+/// Ask for some of each stocked dock's cargo to be taken to the next dock that will accept it.
+pub fn generate_contracts(stocks: &[DockStock]) -> Vec<GeneratedContract> {
+    let mut contracts = Vec::new();
+    for (from, stock) in stocks.iter().enumerate() {
+        let Some(cargo) = stock.cargo else {
+            continue;
+        };
+        if stock.amount == 0 {
+            continue;
+        }
+        let destination = (1..stocks.len())
+            .map(|offset| (from + offset) % stocks.len())
+            .find(|to| stocks[*to].accepts(cargo));
+        if let Some(to) = destination {
+            contracts.push(GeneratedContract {
+                cargo,
+                amount: stock.amount.min(GENERATED_CONTRACT_AMOUNT),
+                from,
+                to,
+                cycles: GENERATED_CONTRACT_CYCLES,
+            });
+        }
+    }
+    contracts
+}
+
+fn track_deliveries(
+    trigger: Trigger<CargoTransferred>,
+    mut commands: Commands,
+    mut ship_query: Query<(&Hold, &mut CargoOrigin)>,
+    mut contracts: ResMut<Contracts>,
+) {
+    let CargoTransferred {
+        ship,
+        dock,
+        cargo,
+        amount,
+        direction,
+    } = *trigger.event();
+    let Ok((hold, mut origin)) = ship_query.get_mut(ship) else {
+        return;
+    };
+
+    match direction {
+        TransferDirection::Load => {
+            // Topping up from a different dock mixes the cargo, so it loses its origin.
+            let was_empty = hold.amount == amount;
+            origin.0 = (was_empty || origin.0 == Some(dock)).then_some(dock);
+        }
+        TransferDirection::Unload => {
+            if let Some(from) = origin.0 {
+                let mut uncounted = amount;
+                for (index, contract) in contracts.0.iter_mut().enumerate() {
+                    if contract.status != ContractStatus::Active
+                        || contract.cargo != cargo
+                        || contract.from != from
+                        || contract.to != dock
+                    {
+                        continue;
+                    }
+                    let counted = uncounted.min(contract.remaining());
+                    contract.delivered += counted;
+                    uncounted -= counted;
+                    if contract.remaining() == 0 {
+                        contract.status = ContractStatus::Completed;
+                        commands.trigger(ContractCompleted { contract: index });
+                    }
+                }
+            }
+            if hold.amount == 0 {
+                origin.0 = None;
+            }
+        }
+    }
+}
+
+pub fn check_contracts(
+    mut commands: Commands,
+    mut contracts: ResMut<Contracts>,
+    cycle_num: Res<CycleNum>,
+    dock_query: Query<&DockStock, With<Dock>>,
+    ship_query: Query<(&Hold, &CargoOrigin)>,
+) {
+    for index in 0..contracts.0.len() {
+        let contract = &contracts.0[index];
+        if contract.status != ContractStatus::Active {
+            continue;
+        }
+
+        if cycle_num.cycle() >= contract.deadline {
+            contracts.0[index].status = ContractStatus::Expired;
+            commands.trigger(ContractExpired { contract: index });
+            continue;
+        }
+
+        let in_stock = dock_query
+            .get(contract.from)
+            .ok()
+            .filter(|stock| stock.cargo == Some(contract.cargo))
+            .map_or(0, |stock| stock.amount);
+        let in_transit: u32 = ship_query
+            .iter()
+            .filter(|(hold, origin)| {
+                origin.0 == Some(contract.from) && hold.cargo == Some(contract.cargo)
+            })
+            .map(|(hold, _)| hold.amount)
+            .sum();
+        let accepted = dock_query
+            .get(contract.to)
+            .is_ok_and(|stock| stock.accepts(contract.cargo));
+        if in_stock + in_transit < contract.remaining() || !accepted {
+            contracts.0[index].status = ContractStatus::Failed;
+            commands.trigger(ContractFailed { contract: index });
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! The daily challenge: a generated harbour that is the same for everyone on a given day.
+//!
+//! Every ship starts with the same actions, so players on the same version of the game
+//! get the same puzzle. Runs are recorded locally, and each one has a short result string
+//! that can be pasted to compare with others. Recording runs is only supported on native.
+
+use crate::game::objectives::{LevelResult, MAX_STARS};
+use crate::game::spawn::harbour::PracticeHarbour;
+use crate::screen::Screen;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<DailyChallenge>();
+    app.insert_resource(DailyRuns(read_daily_runs()));
+    app.add_systems(
+        OnEnter(Screen::Results),
+        record_daily_run.run_if(resource_exists::<DailyChallenge>),
+    );
+    app.add_systems(OnEnter(Screen::Title), end_daily_challenge);
+}
+
+#[cfg(not(target_family = "wasm"))]
+const DAILY_RUNS_FILE: &str = "daily_runs.ron";
+
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+/// Mixed into the day number so the daily harbours aren't the first few practice seeds.
+const DAILY_SEED_SALT: u64 = 0x5348_4950_5049_4e47;
+
+/// The daily challenge being played.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct DailyChallenge {
+    /// Days since the Unix epoch, in UTC so that everyone agrees on the date.
+    pub day: u64,
+}
+
+impl DailyChallenge {
+    pub fn today() -> Self {
+        Self {
+            day: crate::game::save::seconds_since_epoch() / SECONDS_PER_DAY,
+        }
+    }
+
+    /// The harbour to play today.
+    pub fn harbour(&self) -> PracticeHarbour {
+        PracticeHarbour {
+            seed: self.day ^ DAILY_SEED_SALT,
+        }
+    }
+}
+
+/// One attempt at a daily challenge.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyRun {
+    pub day: u64,
+    pub won: bool,
+    pub cycles: usize,
+    pub actions: usize,
+    pub stars: u32,
+}
+
+impl DailyRun {
+    pub fn new(challenge: &DailyChallenge, level_result: &LevelResult) -> Self {
+        Self {
+            day: challenge.day,
+            won: level_result.won,
+            cycles: level_result.cycles,
+            actions: level_result.actions,
+            stars: level_result.stars,
+        }
+    }
+
+    /// A line to share with other players.
+    pub fn result_string(&self) -> String {
+        if self.won {
+            format!(
+                "Shipping Friends daily #{}: cleared in {} cycles with {} actions ({}/{} stars)",
+                self.day, self.cycles, self.actions, self.stars, MAX_STARS
+            )
+        } else {
+            format!(
+                "Shipping Friends daily #{}: failed after {} cycles",
+                self.day, self.cycles
+            )
+        }
+    }
+
+    /// Cleared beats failed, then fewer cycles, then fewer actions.
+    fn rank(&self) -> (bool, std::cmp::Reverse<usize>, std::cmp::Reverse<usize>) {
+        (
+            self.won,
+            std::cmp::Reverse(self.cycles),
+            std::cmp::Reverse(self.actions),
+        )
+    }
+}
+
+/// Every daily challenge run on this device, oldest first.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct DailyRuns(pub Vec<DailyRun>);
+
+impl DailyRuns {
+    /// The best run on `day`, if there has been one.
+    pub fn best(&self, day: u64) -> Option<&DailyRun> {
+        self.0
+            .iter()
+            .filter(|run| run.day == day)
+            .max_by_key(|run| run.rank())
+    }
+}
+
+pub fn record_daily_run(
+    challenge: Res<DailyChallenge>,
+    level_result: Res<LevelResult>,
+    mut daily_runs: ResMut<DailyRuns>,
+) {
+    daily_runs.0.push(DailyRun::new(&challenge, &level_result));
+    if let Err(e) = write_daily_runs(&daily_runs.0) {
+        warn!("Failed to record daily challenge run: {e}");
+    }
+}
+
+fn end_daily_challenge(mut commands: Commands) {
+    commands.remove_resource::<DailyChallenge>();
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn daily_runs_path() -> Option<std::path::PathBuf> {
+    crate::game::save::data_dir().map(|dir| dir.join(DAILY_RUNS_FILE))
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn read_daily_runs() -> Vec<DailyRun> {
+    let Some(contents) = daily_runs_path().and_then(|path| std::fs::read_to_string(path).ok())
+    else {
+        return Vec::new();
+    };
+    ron::from_str(&contents)
+        .inspect_err(|e| warn!("Ignoring unreadable daily challenge runs: {e}"))
+        .unwrap_or_default()
+}
+
+#[cfg(target_family = "wasm")]
+fn read_daily_runs() -> Vec<DailyRun> {
+    Vec::new()
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn write_daily_runs(runs: &[DailyRun]) -> Result<(), String> {
+    let contents = ron::ser::to_string_pretty(runs, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())?;
+    let path = daily_runs_path().ok_or("no data directory")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, contents).map_err(|e| e.to_string())
+}
+
+#[cfg(target_family = "wasm")]
+fn write_daily_runs(_runs: &[DailyRun]) -> Result<(), String> {
+    Err("recording runs is not supported on the web".to_string())
+}
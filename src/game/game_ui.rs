@@ -1,41 +1,54 @@
-use crate::game::movement::{ApplyTurnActions, Facing};
-use crate::screen::Screen;
+use crate::game::cargo::{DockStock, Hold};
+use crate::game::contracts::{
+    Contract, ContractCompleted, ContractExpired, ContractFailed, ContractStatus, Contracts,
+};
+use crate::game::movement::{ApplyTurnActions, Facing, ShipBlocked, ShipWrecked};
+use crate::game::save::{SaveGame, SaveSlot, SAVE_SLOTS};
+use crate::game::simulation::{CollisionResponse, DockState, PROGRAM_LENGTH};
+use crate::game::solver::{plan, Stop};
+use crate::game::spawn::dock::{Dock, DockName};
+use crate::game::spawn::level::LevelWalls;
+use crate::game::spawn::player::{Player, ShipNumber};
+use crate::screen::{PlayingState, Screen};
 use bevy::prelude::*;
 use bevy_ecs_ldtk::GridCoords;
 use bevy_ecs_tilemap::tiles::TilePos;
 use bevy_egui::egui::{vec2, Color32, Frame, Id, Stroke, WidgetText};
 use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 
 pub fn plugin(app: &mut App) {
-    app.init_resource::<GlobalTurnLock>();
     app.init_resource::<CycleNum>();
-    app.register_type::<(GlobalTurnLock, CycleNum)>();
+    app.init_resource::<StatusMessage>();
+    app.init_resource::<SelectedShip>();
+    app.register_type::<(CycleNum, SelectedShip)>();
     app.add_systems(OnEnter(Screen::Playing), setup);
-    app.add_systems(Update, (do_ui).run_if(in_state(Screen::Playing)));
+    app.observe(report_ship_blocked);
+    app.observe(report_ship_wrecked);
+    app.observe(report_contract_completed);
+    app.observe(report_contract_failed);
+    app.observe(report_contract_expired);
+    app.add_systems(
+        Update,
+        (
+            do_ui.run_if(
+                in_state(PlayingState::Planning).or_else(in_state(PlayingState::Executing)),
+            ),
+            do_contracts_ui.run_if(in_state(Screen::Playing)),
+        ),
+    );
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect, Serialize, Deserialize)]
 pub enum TurnAction {
     Forward,
     RotateClockwise,
     RotateAntiClockwise,
-}
-
-#[derive(Clone, Debug, Resource, Reflect)]
-#[reflect(Resource)]
-pub struct GlobalTurnLock {
-    locked: bool,
-}
-impl Default for GlobalTurnLock {
-    fn default() -> Self {
-        Self { locked: false }
-    }
-}
-
-impl GlobalTurnLock {
-    pub fn unlock(&mut self) {
-        self.locked = false;
-    }
+    /// Take on cargo from the crane serving the ship's tile.
+    Load,
+    /// Hand the ship's cargo to the crane serving its tile.
+    Unload,
 }
 
 impl TurnAction {
@@ -57,6 +70,23 @@ impl TurnAction {
                 new_facing.rotate(false);
                 (new_facing, new_coords)
             }
+            TurnAction::Load | TurnAction::Unload => (new_facing, new_coords),
+        }
+    }
+}
+
+impl FromStr for TurnAction {
+    type Err = String;
+
+    /// Parse the variant names, as used in the level's `Actions` field in LDtk.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "Forward" => Ok(Self::Forward),
+            "RotateClockwise" => Ok(Self::RotateClockwise),
+            "RotateAntiClockwise" => Ok(Self::RotateAntiClockwise),
+            "Load" => Ok(Self::Load),
+            "Unload" => Ok(Self::Unload),
+            _ => Err(format!("Unknown action: {s}")),
         }
     }
 }
@@ -67,6 +97,8 @@ impl std::fmt::Display for TurnAction {
             TurnAction::Forward => write!(f, "Forward"),
             TurnAction::RotateClockwise => write!(f, "Rotate Clockwise"),
             TurnAction::RotateAntiClockwise => write!(f, "Rotate Anti-Clockwise"),
+            TurnAction::Load => write!(f, "Load"),
+            TurnAction::Unload => write!(f, "Unload"),
         }
     }
 }
@@ -77,10 +109,10 @@ impl Into<WidgetText> for TurnAction {
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct TurnActions(pub [Option<TurnAction>; 6]);
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TurnActions(pub [Option<TurnAction>; PROGRAM_LENGTH]);
 
-#[derive(Clone, Debug, Resource, Reflect)]
+#[derive(Clone, Debug, Resource, Reflect, Serialize, Deserialize)]
 #[reflect(Resource)]
 pub struct CycleNum {
     cycle_num: usize,
@@ -89,6 +121,16 @@ pub struct CycleNum {
 }
 
 impl CycleNum {
+    /// The number of whole cycles played so far.
+    pub fn cycle(&self) -> usize {
+        self.cycle_num
+    }
+
+    /// The number of steps in each cycle's program.
+    pub fn turns_per_cycle(&self) -> usize {
+        self.turns_per_cycle
+    }
+
     pub fn increment(&mut self) {
         self.turn_num += 1;
         if self.turn_num >= self.turns_per_cycle {
@@ -110,37 +152,140 @@ impl Default for CycleNum {
         Self {
             cycle_num: 0,
             turn_num: 0,
-            turns_per_cycle: 6,
+            turns_per_cycle: PROGRAM_LENGTH,
         }
     }
 }
 
+/// The most recent notable thing that happened, shown under the turn counter.
+#[derive(Clone, Debug, Default, Resource)]
+pub struct StatusMessage(pub Option<String>);
+
+fn report_ship_blocked(trigger: Trigger<ShipBlocked>, mut status_message: ResMut<StatusMessage>) {
+    let ShipBlocked {
+        at,
+        other_ship,
+        response,
+        ..
+    } = trigger.event();
+    let obstacle = match other_ship {
+        Some(_) => "another ship",
+        None => "a wall",
+    };
+    let consequence = match response {
+        CollisionResponse::Skipped => "action skipped".to_string(),
+        CollisionResponse::Aborted => "program aborted".to_string(),
+        CollisionResponse::BouncedBack { .. } => "bounced back".to_string(),
+        CollisionResponse::Damaged { hull } => format!("hull at {hull}"),
+    };
+    status_message.0 = Some(format!(
+        "Blocked by {} at ({}, {}): {}",
+        obstacle, at.x, at.y, consequence
+    ));
+}
+
+fn report_ship_wrecked(
+    trigger: Trigger<ShipWrecked>,
+    ship_numbers: Query<&ShipNumber>,
+    mut status_message: ResMut<StatusMessage>,
+) {
+    let name = match ship_numbers.get(trigger.event().ship) {
+        Ok(number) => format!("Ship {}", number.0 + 1),
+        Err(_) => "A ship".to_string(),
+    };
+    status_message.0 = Some(format!("{name} was wrecked"));
+}
+
+fn report_contract_completed(
+    trigger: Trigger<ContractCompleted>,
+    contracts: Res<Contracts>,
+    dock_names: Query<&DockName>,
+    mut status_message: ResMut<StatusMessage>,
+) {
+    let contract = &contracts.0[trigger.event().contract];
+    status_message.0 = Some(format!(
+        "Contract completed: {}",
+        contract_label(contract, &dock_names)
+    ));
+}
+
+fn report_contract_failed(
+    trigger: Trigger<ContractFailed>,
+    contracts: Res<Contracts>,
+    dock_names: Query<&DockName>,
+    mut status_message: ResMut<StatusMessage>,
+) {
+    let contract = &contracts.0[trigger.event().contract];
+    status_message.0 = Some(format!(
+        "Contract failed: {}",
+        contract_label(contract, &dock_names)
+    ));
+}
+
+fn report_contract_expired(
+    trigger: Trigger<ContractExpired>,
+    contracts: Res<Contracts>,
+    dock_names: Query<&DockName>,
+    mut status_message: ResMut<StatusMessage>,
+) {
+    let contract = &contracts.0[trigger.event().contract];
+    status_message.0 = Some(format!(
+        "Contract expired: {}",
+        contract_label(contract, &dock_names)
+    ));
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 enum ColumnName {
     Active,
     Store,
 }
 
-#[derive(Clone, Debug, Resource)]
+/// The ship whose program is shown in the editor.
+#[derive(Clone, Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct SelectedShip(pub Option<Entity>);
+
+/// A ship's program for the next cycle, and the actions it has left to program with.
+#[derive(Clone, Debug, Component, Serialize, Deserialize)]
 pub struct CycleStore {
     turn_actions: TurnActions,
     store: Vec<TurnAction>,
 }
 
+impl Default for CycleStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CycleStore {
+    /// A store with the usual set of actions, for levels that don't choose their own.
     pub fn new() -> Self {
+        Self::with_actions(vec![
+            TurnAction::Forward,
+            TurnAction::Forward,
+            TurnAction::Forward,
+            TurnAction::RotateClockwise,
+            TurnAction::RotateAntiClockwise,
+            TurnAction::Load,
+            TurnAction::Unload,
+        ])
+    }
+
+    /// A store holding `actions`, with nothing programmed yet.
+    pub fn with_actions(actions: Vec<TurnAction>) -> Self {
         Self {
-            turn_actions: TurnActions([None; 6]),
-            store: vec![
-                TurnAction::Forward,
-                TurnAction::Forward,
-                TurnAction::Forward,
-                TurnAction::RotateClockwise,
-                TurnAction::RotateAntiClockwise,
-            ],
+            turn_actions: TurnActions([None; PROGRAM_LENGTH]),
+            store: actions,
         }
     }
 
+    /// The program as it stands in the Active column.
+    pub fn turn_actions(&self) -> &TurnActions {
+        &self.turn_actions
+    }
+
     fn clear(&mut self) {
         for elt in self.turn_actions.0.iter_mut() {
             if let Some(elt) = elt {
@@ -150,6 +295,29 @@ impl CycleStore {
         }
     }
 
+    /// Every action this ship has to program with, whether in the program or the store.
+    pub fn available(&self) -> Vec<TurnAction> {
+        self.turn_actions
+            .0
+            .iter()
+            .flatten()
+            .chain(&self.store)
+            .copied()
+            .collect()
+    }
+
+    /// Replace the program with `program`, taking its actions from the store.
+    /// Whatever was programmed before goes back to the store first.
+    pub fn set_program(&mut self, program: &[TurnAction]) {
+        self.clear();
+        for (slot, action) in self.turn_actions.0.iter_mut().zip(program) {
+            if let Some(idx) = self.store.iter().position(|stored| stored == action) {
+                self.store.remove(idx);
+                *slot = Some(*action);
+            }
+        }
+    }
+
     fn take(&mut self, col: ColumnName, row: usize) -> Option<TurnAction> {
         match col {
             ColumnName::Active => {
@@ -165,7 +333,7 @@ impl CycleStore {
 
     fn take_turn_actions(&mut self) -> TurnActions {
         let populated = self.turn_actions.clone();
-        self.turn_actions = TurnActions([None; 6]);
+        self.turn_actions = TurnActions([None; PROGRAM_LENGTH]);
         populated
     }
 
@@ -189,168 +357,370 @@ struct Location {
 }
 
 fn setup(mut commands: Commands) {
-    commands.insert_resource(CycleStore::new());
+    commands.insert_resource(CycleNum::default());
+    commands.insert_resource(StatusMessage::default());
+    commands.insert_resource(SelectedShip::default());
 }
 
 fn do_ui(
     mut commands: Commands,
     mut contexts: EguiContexts,
-    mut cycle_store: ResMut<CycleStore>,
+    mut ship_query: Query<
+        (
+            Entity,
+            &ShipNumber,
+            &Hold,
+            &Facing,
+            &GridCoords,
+            &mut CycleStore,
+        ),
+        With<Player>,
+    >,
+    dock_query: Query<(&Facing, &GridCoords, &DockStock, &DockName), With<Dock>>,
+    mut selected_ship: ResMut<SelectedShip>,
     cycle_num: Res<CycleNum>,
-    mut global_turn_lock: ResMut<GlobalTurnLock>,
+    mut status_message: ResMut<StatusMessage>,
+    playing_state: Res<State<PlayingState>>,
+    contracts: Res<Contracts>,
+    level_walls: Res<LevelWalls>,
 ) {
+    let locked = *playing_state.get() != PlayingState::Planning;
     egui::Window::new("Game UI")
         .anchor(egui::Align2::RIGHT_BOTTOM, vec2(0.0, 0.0))
         .show(contexts.ctx_mut(), |ui| {
-            // If there is a drop, store the location of the item being dragged, and the destination for the drop.
-            let mut from = None;
-            let mut to = None;
-
             ui.label(cycle_num.display_cycle_num());
             ui.label(cycle_num.display_turn_num());
+            if let Some(message) = &status_message.0 {
+                ui.label(message);
+            }
 
-            ui.columns(2, |uis| {
-                if global_turn_lock.locked {
-                    for ui in uis.iter_mut() {
-                        ui.disable();
+            ui.horizontal(|ui| {
+                let mut ships: Vec<(Entity, ShipNumber)> = ship_query
+                    .iter()
+                    .map(|(entity, number, ..)| (entity, *number))
+                    .collect();
+                ships.sort_by_key(|(_, number)| *number);
+                for (entity, number) in ships {
+                    let selected = selected_ship.0 == Some(entity);
+                    if ui
+                        .selectable_label(selected, format!("Ship {}", number.0 + 1))
+                        .clicked()
+                    {
+                        selected_ship.0 = Some(entity);
                     }
                 }
+            });
 
-                // Active Column
-                {
-                    let ui = &mut uis[0];
-                    let frame = Frame::default().inner_margin(4.0);
-
-                    for (idx, elt) in cycle_store.turn_actions.0.iter().enumerate() {
-                        let (_, dropped_payload) = ui.dnd_drop_zone::<Location, ()>(frame, |ui| {
-                            ui.set_min_size(vec2(64.0, 30.0));
-
-                            let item_id = Id::new(("drag_and_drop_cycle", ColumnName::Active, idx));
-                            let item_location = Location {
-                                col: ColumnName::Active,
-                                row: idx,
-                            };
-                            let _label: WidgetText = match elt {
-                                Some(action) => (*action).into(),
-                                None => "Wait".into(),
-                            };
-                            match elt {
-                                Some(action) => {
-                                    ui.dnd_drag_source(item_id, item_location, |ui| {
-                                        ui.label(*action);
-                                    });
-                                }
-                                None => {
-                                    ui.label("Wait");
-                                }
-                            }
-                        });
-                        if let Some(dragged_payload) = dropped_payload {
-                            // The user dropped onto the whole area, which is what we want for this
-                            from = Some(dragged_payload);
-                            to = Some(Location {
-                                col: ColumnName::Active,
-                                row: idx,
-                            });
+            if let Some(entity) = selected_ship.0 {
+                if let Ok((_, _, hold, _, _, mut cycle_store)) = ship_query.get_mut(entity) {
+                    ui.label(hold_label(hold));
+                    program_editor(ui, &mut cycle_store, locked);
+                }
+            }
+
+            ui.horizontal(|ui| {
+                if locked {
+                    ui.disable();
+                }
+                if ui.button("Clear").clicked() {
+                    if let Some(entity) = selected_ship.0 {
+                        if let Ok((.., mut cycle_store)) = ship_query.get_mut(entity) {
+                            cycle_store.clear();
                         }
                     }
                 }
-
-                // Store Column
-                {
-                    let ui = &mut uis[1];
-
-                    let frame = Frame::default().inner_margin(4.0);
-
-                    let (_, dropped_payload) = ui.dnd_drop_zone::<Location, ()>(frame, |ui| {
-                        ui.set_min_size(vec2(64.0, 100.0));
-                        for (row_idx, item) in cycle_store.store.iter().enumerate() {
-                            let item_id =
-                                Id::new(("drag_and_drop_cycle", ColumnName::Store, row_idx));
-                            let item_location = Location {
-                                col: ColumnName::Store,
-                                row: row_idx,
-                            };
-                            let response = ui
-                                .dnd_drag_source(item_id, item_location, |ui| {
-                                    // I'd like to have a frame and whatnot here, but adding
-                                    // frames results in a debug assertion failure.
-                                    // See: https://github.com/emilk/egui/issues/4604
-
-                                    ui.label(*item);
-                                })
-                                .response;
-
-                            // Detect drops onto this item:
-                            if let (Some(pointer), Some(hovered_payload)) = (
-                                ui.input(|i| i.pointer.interact_pos()),
-                                response.dnd_hover_payload::<Location>(),
-                            ) {
-                                let rect = response.rect;
-
-                                // Preview insertion:
-                                let stroke = egui::Stroke::new(1.0, Color32::WHITE);
-                                let insert_row_idx = if *hovered_payload == item_location {
-                                    // We are dragged onto ourselves
-                                    ui.painter().hline(rect.x_range(), rect.center().y, stroke);
-                                    row_idx
-                                } else if pointer.y < rect.center().y {
-                                    // Above us
-                                    ui.painter().hline(rect.x_range(), rect.top(), stroke);
-                                    row_idx
-                                } else {
-                                    // Below us
-                                    ui.painter().hline(rect.x_range(), rect.bottom(), stroke);
-                                    row_idx + 1
-                                };
-
-                                if let Some(dragged_payload) = response.dnd_release_payload() {
-                                    // The user dropped onto this item.
-                                    from = Some(dragged_payload);
-                                    to = Some(Location {
-                                        col: ColumnName::Store,
-                                        row: insert_row_idx,
-                                    });
-                                }
-                            }
+                if ui.button("Hint").clicked() {
+                    if let Some(entity) = selected_ship.0 {
+                        if let Ok((_, _, hold, facing, coords, mut cycle_store)) =
+                            ship_query.get_mut(entity)
+                        {
+                            status_message.0 = Some(give_hint(
+                                &mut cycle_store,
+                                *facing,
+                                *coords,
+                                hold,
+                                &contracts,
+                                &dock_query,
+                                &level_walls,
+                                cycle_num.turns_per_cycle(),
+                            ));
                         }
-                    });
-
-                    if let Some(dragged_payload) = dropped_payload {
-                        // The user dropped onto the column, but not on any one item.
-                        from = Some(dragged_payload);
-                        to = Some(Location {
-                            col: ColumnName::Store,
-                            row: cycle_store.store.len(),
-                        });
                     }
                 }
+                if ui.button("Go").clicked() {
+                    let programs = ship_query
+                        .iter_mut()
+                        .map(|(entity, .., mut cycle_store)| {
+                            (entity, cycle_store.take_turn_actions())
+                        })
+                        .collect();
+                    commands.trigger(ApplyTurnActions(programs));
+                }
             });
 
-            if let (Some(from), Some(mut to)) = (from, to) {
-                if from.col == to.col {
-                    // Dragging within the same column.
-                    // Adjust row index if we are re-ordering:
-                    to.row -= (from.row < to.row) as usize;
+            ui.horizontal(|ui| {
+                if locked {
+                    ui.disable();
                 }
-
-                //let item = cycle_store.get_column_mut(from.col).remove(from.row);
-                if let Some(item) = cycle_store.take(from.col, from.row) {
-                    cycle_store.add(to.col, to.row, item);
+                ui.label("Save to");
+                for slot in 1..=SAVE_SLOTS {
+                    if ui.button(format!("{slot}")).clicked() {
+                        commands.trigger(SaveGame(SaveSlot::Manual(slot)));
+                    }
                 }
+            });
+        });
+}
+
+/// Program a ship with the first cycle of the quickest way to make its next delivery,
+/// and say where it's headed.
+fn give_hint(
+    cycle_store: &mut CycleStore,
+    facing: Facing,
+    coords: GridCoords,
+    hold: &Hold,
+    contracts: &Contracts,
+    dock_query: &Query<(&Facing, &GridCoords, &DockStock, &DockName), With<Dock>>,
+    level_walls: &LevelWalls,
+    turns_per_cycle: usize,
+) -> String {
+    let dock = |entity| {
+        let (facing, coords, stock, name) = dock_query.get(entity).ok()?;
+        Some((DockState::new(*coords, *facing, *stock), name))
+    };
+    let Some(((from, from_name), (to, to_name))) = hint_contract(hold, contracts)
+        .and_then(|contract| Some((dock(contract.from)?, dock(contract.to)?)))
+    else {
+        return "Hint: no contract needs this ship".to_string();
+    };
+    // A ship with cargo only has to drop it off.
+    let (stops, name) = match hold.cargo {
+        Some(_) => (vec![Stop::unload_at(&to)], to_name),
+        None => (vec![Stop::load_at(&from), Stop::unload_at(&to)], from_name),
+    };
+    match plan(
+        level_walls,
+        facing,
+        coords,
+        &stops,
+        &cycle_store.available(),
+        turns_per_cycle,
+        None,
+    ) {
+        Some(plan) => {
+            cycle_store.set_program(plan.first_program());
+            match plan.cycles() {
+                1 => format!("Hint: head to {}", name.0),
+                cycles => format!(
+                    "Hint: head to {}, the delivery takes {} cycles",
+                    name.0, cycles
+                ),
             }
+        }
+        None => "Hint: no hint available".to_string(),
+    }
+}
 
-            ui.horizontal(|ui| {
-                if global_turn_lock.locked {
-                    ui.disable();
+/// The contract a ship should work on next: the one its cargo is wanted for if it has any,
+/// otherwise the first unfinished one.
+fn hint_contract<'a>(hold: &Hold, contracts: &'a Contracts) -> Option<&'a Contract> {
+    contracts
+        .0
+        .iter()
+        .filter(|contract| contract.status == ContractStatus::Active)
+        .find(|contract| hold.cargo.map_or(true, |cargo| cargo == contract.cargo))
+}
+
+fn do_contracts_ui(
+    mut contexts: EguiContexts,
+    contracts: Res<Contracts>,
+    dock_names: Query<&DockName>,
+    cycle_num: Res<CycleNum>,
+) {
+    egui::Window::new("Contracts")
+        .anchor(egui::Align2::RIGHT_TOP, vec2(0.0, 0.0))
+        .show(contexts.ctx_mut(), |ui| {
+            if contracts.0.is_empty() {
+                ui.label("No contracts");
+            }
+            for contract in &contracts.0 {
+                ui.label(contract_label(contract, &dock_names));
+                let progress = format!("{}/{} delivered", contract.delivered, contract.amount);
+                match contract.status {
+                    ContractStatus::Active => {
+                        let cycles_left = contract.deadline.saturating_sub(cycle_num.cycle());
+                        ui.label(format!("{progress}, {cycles_left} cycles left"));
+                    }
+                    ContractStatus::Completed => {
+                        ui.colored_label(Color32::GREEN, "Completed");
+                    }
+                    ContractStatus::Failed => {
+                        ui.colored_label(Color32::RED, format!("Failed ({progress})"));
+                    }
+                    ContractStatus::Expired => {
+                        ui.colored_label(Color32::RED, format!("Expired ({progress})"));
+                    }
                 }
-                if ui.button("Clear").clicked() {
-                    cycle_store.clear();
+                ui.separator();
+            }
+        });
+}
+
+fn contract_label(contract: &Contract, dock_names: &Query<&DockName>) -> String {
+    let dock_name = |dock| {
+        dock_names
+            .get(dock)
+            .map_or("?".to_string(), |name| name.0.clone())
+    };
+    format!(
+        "{} {} from {} to {}",
+        contract.amount,
+        contract.cargo,
+        dock_name(contract.from),
+        dock_name(contract.to)
+    )
+}
+
+fn hold_label(hold: &Hold) -> String {
+    match hold.cargo {
+        Some(cargo) => format!("Hold: {}/{} {}", hold.amount, hold.capacity, cargo),
+        None => format!("Hold: empty ({} capacity)", hold.capacity),
+    }
+}
+
+/// The drag-and-drop editor for a single ship's program.
+fn program_editor(ui: &mut egui::Ui, cycle_store: &mut CycleStore, locked: bool) {
+    // If there is a drop, store the location of the item being dragged, and the destination for the drop.
+    let mut from = None;
+    let mut to = None;
+
+    ui.columns(2, |uis| {
+        if locked {
+            for ui in uis.iter_mut() {
+                ui.disable();
+            }
+        }
+
+        // Active Column
+        {
+            let ui = &mut uis[0];
+            let frame = Frame::default().inner_margin(4.0);
+
+            for (idx, elt) in cycle_store.turn_actions.0.iter().enumerate() {
+                let (_, dropped_payload) = ui.dnd_drop_zone::<Location, ()>(frame, |ui| {
+                    ui.set_min_size(vec2(64.0, 30.0));
+
+                    let item_id = Id::new(("drag_and_drop_cycle", ColumnName::Active, idx));
+                    let item_location = Location {
+                        col: ColumnName::Active,
+                        row: idx,
+                    };
+                    let _label: WidgetText = match elt {
+                        Some(action) => (*action).into(),
+                        None => "Wait".into(),
+                    };
+                    match elt {
+                        Some(action) => {
+                            ui.dnd_drag_source(item_id, item_location, |ui| {
+                                ui.label(*action);
+                            });
+                        }
+                        None => {
+                            ui.label("Wait");
+                        }
+                    }
+                });
+                if let Some(dragged_payload) = dropped_payload {
+                    // The user dropped onto the whole area, which is what we want for this
+                    from = Some(dragged_payload);
+                    to = Some(Location {
+                        col: ColumnName::Active,
+                        row: idx,
+                    });
                 }
-                if ui.button("Go").clicked() {
-                    global_turn_lock.locked = true;
-                    commands.trigger(ApplyTurnActions(cycle_store.take_turn_actions()));
+            }
+        }
+
+        // Store Column
+        {
+            let ui = &mut uis[1];
+
+            let frame = Frame::default().inner_margin(4.0);
+
+            let (_, dropped_payload) = ui.dnd_drop_zone::<Location, ()>(frame, |ui| {
+                ui.set_min_size(vec2(64.0, 100.0));
+                for (row_idx, item) in cycle_store.store.iter().enumerate() {
+                    let item_id = Id::new(("drag_and_drop_cycle", ColumnName::Store, row_idx));
+                    let item_location = Location {
+                        col: ColumnName::Store,
+                        row: row_idx,
+                    };
+                    let response = ui
+                        .dnd_drag_source(item_id, item_location, |ui| {
+                            // I'd like to have a frame and whatnot here, but adding
+                            // frames results in a debug assertion failure.
+                            // See: https://github.com/emilk/egui/issues/4604
+
+                            ui.label(*item);
+                        })
+                        .response;
+
+                    // Detect drops onto this item:
+                    if let (Some(pointer), Some(hovered_payload)) = (
+                        ui.input(|i| i.pointer.interact_pos()),
+                        response.dnd_hover_payload::<Location>(),
+                    ) {
+                        let rect = response.rect;
+
+                        // Preview insertion:
+                        let stroke = egui::Stroke::new(1.0, Color32::WHITE);
+                        let insert_row_idx = if *hovered_payload == item_location {
+                            // We are dragged onto ourselves
+                            ui.painter().hline(rect.x_range(), rect.center().y, stroke);
+                            row_idx
+                        } else if pointer.y < rect.center().y {
+                            // Above us
+                            ui.painter().hline(rect.x_range(), rect.top(), stroke);
+                            row_idx
+                        } else {
+                            // Below us
+                            ui.painter().hline(rect.x_range(), rect.bottom(), stroke);
+                            row_idx + 1
+                        };
+
+                        if let Some(dragged_payload) = response.dnd_release_payload() {
+                            // The user dropped onto this item.
+                            from = Some(dragged_payload);
+                            to = Some(Location {
+                                col: ColumnName::Store,
+                                row: insert_row_idx,
+                            });
+                        }
+                    }
                 }
             });
-        });
+
+            if let Some(dragged_payload) = dropped_payload {
+                // The user dropped onto the column, but not on any one item.
+                from = Some(dragged_payload);
+                to = Some(Location {
+                    col: ColumnName::Store,
+                    row: cycle_store.store.len(),
+                });
+            }
+        }
+    });
+
+    if let (Some(from), Some(mut to)) = (from, to) {
+        if from.col == to.col {
+            // Dragging within the same column.
+            // Adjust row index if we are re-ordering:
+            to.row -= (from.row < to.row) as usize;
+        }
+
+        //let item = cycle_store.get_column_mut(from.col).remove(from.row);
+        if let Some(item) = cycle_store.take(from.col, from.row) {
+            cycle_store.add(to.col, to.row, item);
+        }
+    }
 }
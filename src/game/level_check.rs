@@ -0,0 +1,366 @@
+//! Offline checks that levels can be beaten, for the `check_levels` tool.
+//! [`read_level`] is also used to play replays back without spawning their level.
+//! Levels are read straight from the LDtk data the same way the game spawns them,
+//! so no `App` or window is needed.
+//!
+//! Each objective is checked on its own, for whichever ship can do it soonest, using the
+//! actions the level gives each ship to spend, and as many turns per cycle as the game plays.
+//! Other ships are not taken into account.
+
+use crate::game::cargo::{DockStock, Hold};
+use crate::game::contracts::{generate_contracts, ContractSpec};
+use crate::game::game_ui::CycleNum;
+use crate::game::movement::Facing;
+use crate::game::simulation::DockState;
+use crate::game::solver::{solve, solve_delivery, Plan};
+use crate::game::spawn::dock::DockName;
+use crate::game::spawn::level::{LevelRules, LevelWalls, GRID_SIZE, WALL_INT_CELL};
+use bevy::utils::hashbrown::HashSet;
+use bevy_ecs_ldtk::ldtk::ldtk_fields::LdtkFields;
+use bevy_ecs_ldtk::ldtk::Level;
+use bevy_ecs_ldtk::utils::{int_grid_index_to_grid_coords, ldtk_grid_coords_to_grid_coords};
+use bevy_ecs_ldtk::GridCoords;
+use std::fmt;
+
+/// The quickest way found to meet an objective.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Route {
+    /// The fewest cycles a single ship needs.
+    pub cycles: usize,
+    /// The fewest actions it needs within those cycles.
+    pub actions: usize,
+}
+
+impl From<&Plan> for Route {
+    fn from(plan: &Plan) -> Self {
+        Self {
+            cycles: plan.cycles(),
+            actions: plan.actions(),
+        }
+    }
+}
+
+/// How a single objective of a level fared.
+#[derive(Clone, Debug)]
+pub struct ObjectiveCheck {
+    pub description: String,
+    /// The quickest route, or why there isn't one.
+    pub route: Result<Route, String>,
+    /// The number of cycles it has to be done within, if there is a limit.
+    pub limit: Option<usize>,
+}
+
+impl ObjectiveCheck {
+    fn new(description: String, route: Result<Route, String>, limit: Option<usize>) -> Self {
+        Self {
+            description,
+            route,
+            limit,
+        }
+    }
+
+    pub fn is_possible(&self) -> bool {
+        self.route
+            .as_ref()
+            .is_ok_and(|route| self.limit.map_or(true, |limit| route.cycles <= limit))
+    }
+}
+
+/// The results of checking one level.
+#[derive(Clone, Debug)]
+pub struct LevelCheck {
+    pub level: String,
+    /// The number of actions a ship can play in each cycle.
+    pub turns_per_cycle: usize,
+    pub objectives: Vec<ObjectiveCheck>,
+}
+
+impl LevelCheck {
+    pub fn is_possible(&self) -> bool {
+        self.objectives.iter().all(ObjectiveCheck::is_possible)
+    }
+
+    /// Whether there is nothing to do, or everything can be done within the first cycle.
+    pub fn is_trivial(&self) -> bool {
+        self.objectives.iter().all(|objective| {
+            objective
+                .route
+                .as_ref()
+                .is_ok_and(|route| route.cycles <= 1)
+        })
+    }
+}
+
+impl fmt::Display for LevelCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict = if !self.is_possible() {
+            "IMPOSSIBLE"
+        } else if self.is_trivial() {
+            "TRIVIAL"
+        } else {
+            "ok"
+        };
+        writeln!(
+            f,
+            "{}: {} ({} turns per cycle)",
+            self.level, verdict, self.turns_per_cycle
+        )?;
+        if self.objectives.is_empty() {
+            writeln!(f, "  No objectives")?;
+        }
+        for objective in &self.objectives {
+            write!(f, "  {}: ", objective.description)?;
+            match &objective.route {
+                Ok(route) => write!(f, "{} actions, {} cycles", route.actions, route.cycles)?,
+                Err(reason) => write!(f, "{reason}")?,
+            }
+            if let Some(limit) = objective.limit {
+                write!(f, " (limit {limit})")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// The parts of an LDtk level that the rules care about, read without spawning it.
+#[derive(Clone, Debug)]
+pub struct LevelLayout {
+    pub rules: LevelRules,
+    pub walls: LevelWalls,
+    /// Sorted by iid, the same order as the ships' [`ShipNumber`]s.
+    ///
+    /// [`ShipNumber`]: crate::game::spawn::player::ShipNumber
+    pub ships: Vec<(String, Facing, GridCoords)>,
+    /// Sorted by iid, the same order the simulation lists docks in.
+    pub docks: Vec<(String, DockName, DockState)>,
+    pub goals: Vec<GridCoords>,
+}
+
+/// Read a level's walls, ships, docks and goals.
+/// The level has to have its layers inline rather than in a separate file.
+pub fn read_level(level: &Level) -> LevelLayout {
+    let mut wall_locations = HashSet::new();
+    let mut ships = Vec::new();
+    let mut docks = Vec::new();
+    let mut goals = Vec::new();
+    for layer in level.layer_instances.as_deref().unwrap_or_default() {
+        // The same cells that `cache_wall_locations` finds as spawned `Wall`s.
+        for (index, value) in layer.int_grid_csv.iter().enumerate() {
+            if *value == WALL_INT_CELL {
+                wall_locations.extend(int_grid_index_to_grid_coords(
+                    index,
+                    layer.c_wid as u32,
+                    layer.c_hei as u32,
+                ));
+            }
+        }
+        for entity in &layer.entity_instances {
+            let coords = ldtk_grid_coords_to_grid_coords(entity.grid, layer.c_hei);
+            match entity.identifier.as_str() {
+                "Ship" => ships.push((entity.iid.clone(), Facing::from(entity), coords)),
+                "Goal" => goals.push(coords),
+                identifier if identifier.starts_with("Crane_") => docks.push((
+                    entity.iid.clone(),
+                    DockName::from(entity),
+                    DockState::new(coords, Facing::from(entity), DockStock::from(entity)),
+                )),
+                _ => {}
+            }
+        }
+    }
+    ships.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+    docks.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+
+    LevelLayout {
+        rules: LevelRules::from_level(level),
+        walls: LevelWalls::new(
+            wall_locations,
+            level.px_wid / GRID_SIZE,
+            level.px_hei / GRID_SIZE,
+        ),
+        ships,
+        docks,
+        goals,
+    }
+}
+
+/// Check whether each of a level's objectives can be reached, and in how many cycles.
+/// The level has to have its layers inline rather than in a separate file.
+pub fn check_level(level: &Level) -> LevelCheck {
+    let LevelLayout {
+        rules,
+        walls,
+        ships,
+        docks,
+        goals,
+    } = read_level(level);
+    let inventory = rules.inventory();
+    let turns_per_cycle = CycleNum::default().turns_per_cycle();
+
+    let mut objectives = Vec::new();
+    for goal in &goals {
+        let route = ships
+            .iter()
+            .filter_map(|(_, facing, coords)| {
+                solve(
+                    &walls,
+                    *facing,
+                    *coords,
+                    *goal,
+                    &inventory,
+                    turns_per_cycle,
+                    None,
+                )
+            })
+            .map(|plan| Route::from(&plan))
+            .min()
+            .ok_or_else(|| "no ship can get there".to_string());
+        objectives.push(ObjectiveCheck::new(
+            format!("Reach the goal at ({}, {})", goal.x, goal.y),
+            route,
+            rules.cycle_budget,
+        ));
+    }
+
+    if rules.complete_contracts {
+        let find_dock = |name: &str| {
+            docks
+                .iter()
+                .position(|(_, dock_name, _)| dock_name.0 == name)
+        };
+        let mut contracts = Vec::new();
+        let specs = level
+            .get_maybe_strings_field("Contracts")
+            .unwrap_or_default();
+        for spec in specs.iter().flatten() {
+            let spec = match spec.parse::<ContractSpec>() {
+                Ok(spec) => spec,
+                Err(e) => {
+                    objectives.push(ObjectiveCheck::new(
+                        format!("Contract {spec:?}"),
+                        Err(e),
+                        None,
+                    ));
+                    continue;
+                }
+            };
+            let (Some(from), Some(to)) = (find_dock(&spec.from), find_dock(&spec.to)) else {
+                objectives.push(ObjectiveCheck::new(
+                    format!(
+                        "Deliver {} {} from {} to {}",
+                        spec.amount, spec.cargo, spec.from, spec.to
+                    ),
+                    Err("unknown dock".to_string()),
+                    None,
+                ));
+                continue;
+            };
+            contracts.push((spec.amount, spec.cargo, from, to, spec.cycles));
+        }
+        // Levels without contracts of their own get generated ones, like in the game.
+        if contracts.is_empty() {
+            let stocks: Vec<_> = docks.iter().map(|(.., dock)| dock.stock).collect();
+            contracts = generate_contracts(&stocks)
+                .into_iter()
+                .map(|generated| {
+                    (
+                        generated.amount,
+                        generated.cargo,
+                        generated.from,
+                        generated.to,
+                        generated.cycles,
+                    )
+                })
+                .collect();
+        }
+
+        for (amount, cargo, from, to, cycles) in contracts {
+            let (_, from_name, from_dock) = &docks[from];
+            let (_, to_name, to_dock) = &docks[to];
+            let route = if from_dock.stock.cargo != Some(cargo) || from_dock.stock.amount < amount {
+                Err(format!("{} doesn't have the cargo", from_name.0))
+            } else if !to_dock.stock.accepts(cargo) {
+                Err(format!("{} won't take the cargo", to_name.0))
+            } else if amount > Hold::default().capacity {
+                Err("needs more than one trip".to_string())
+            } else {
+                ships
+                    .iter()
+                    .filter_map(|(_, facing, coords)| {
+                        solve_delivery(
+                            &walls,
+                            *facing,
+                            *coords,
+                            from_dock,
+                            to_dock,
+                            &inventory,
+                            turns_per_cycle,
+                            None,
+                        )
+                    })
+                    .map(|plan| Route::from(&plan))
+                    .min()
+                    .ok_or_else(|| "no ship can make the delivery".to_string())
+            };
+            let limit = rules
+                .cycle_budget
+                .map_or(cycles, |budget| budget.min(cycles));
+            objectives.push(ObjectiveCheck::new(
+                format!(
+                    "Deliver {} {} from {} to {}",
+                    amount, cargo, from_name.0, to_name.0
+                ),
+                route,
+                Some(limit),
+            ));
+        }
+    }
+
+    LevelCheck {
+        level: level.identifier.clone(),
+        turns_per_cycle,
+        objectives,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs_ldtk::ldtk::{FieldValue, LdtkJson};
+
+    fn project() -> LdtkJson {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/levels/maps.ldtk");
+        let json = std::fs::read_to_string(path).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn every_level_can_be_beaten() {
+        let project = project();
+        let levels: Vec<_> = project
+            .levels
+            .iter()
+            .chain(project.worlds.iter().flat_map(|world| &world.levels))
+            .collect();
+        assert!(!levels.is_empty());
+        for level in levels {
+            let check = check_level(level);
+            assert!(check.is_possible(), "{check}");
+            assert!(!check.is_trivial(), "{check}");
+        }
+    }
+
+    #[test]
+    fn levels_check_with_their_own_actions() {
+        let mut level = project().levels.remove(0);
+        let actions = level
+            .field_instances
+            .iter_mut()
+            .find(|field| field.identifier == "Actions")
+            .unwrap();
+        // Without Forward, no ship can go anywhere.
+        actions.value = FieldValue::Strings(vec![Some("RotateClockwise".to_string())]);
+        assert!(!check_level(&level).is_possible());
+    }
+}
@@ -6,21 +6,45 @@ mod animation;
 pub mod assets;
 pub mod audio;
 mod camera;
+pub mod campaign;
+pub mod cargo;
+pub mod contracts;
 pub mod controls;
-mod game_ui;
-mod movement;
+pub mod daily;
+pub(crate) mod game_ui;
+pub mod level_check;
+pub(crate) mod movement;
+pub mod objectives;
+mod path_preview;
+pub mod replay;
+pub mod rng;
+pub mod save;
 pub mod settings;
+pub mod simulation;
+pub mod solver;
 pub mod spawn;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins((
         animation::plugin,
         audio::plugin,
+        cargo::plugin,
+        contracts::plugin,
+        daily::plugin,
         assets::plugin,
         movement::plugin,
+        objectives::plugin,
+        path_preview::plugin,
+        replay::plugin,
+        rng::plugin,
+        save::plugin,
         spawn::plugin,
+    ));
+    app.add_plugins((
         controls::plugin,
         camera::plugin,
+        campaign::plugin,
         game_ui::plugin,
+        settings::plugin,
     ));
 }
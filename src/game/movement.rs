@@ -1,29 +1,45 @@
-//! Handle player input and translate it into movement.
-//! Note that the approach used here is simple for demonstration purposes.
-//! If you want to move the player in a smoother way,
-//! consider using a [fixed timestep](https://github.com/bevyengine/bevy/blob/latest/examples/movement/physics_in_fixed_timestep.rs).
-
-use crate::game::controls::{CameraAction, PlayerAction};
-use crate::game::game_ui::{CycleNum, GlobalTurnLock, TurnAction, TurnActions};
-use crate::game::spawn::level::{LevelWalls, TilemapOffset, GRID_SIZE_V};
+//! Play each ship's program out one step at a time, and keep ships' transforms in step
+//! with their position on the grid. Ships only ever move by running their programs.
+
+use crate::game::animation::{RotationTween, TranslationTween};
+use crate::game::cargo::{CargoTransferred, DockStock, Hold, TransferDirection};
+use crate::game::game_ui::{CycleNum, TurnActions};
+use crate::game::settings::Settings;
+use crate::game::simulation::{
+    Board, CollisionResponse, DockState, ShipState, SimEventKind, Simulation,
+};
+use crate::game::spawn::dock::Dock;
+use crate::game::spawn::level::{LevelRules, LevelWalls, GRID_SIZE_V};
+use crate::game::spawn::player::{Hull, Player, ShipNumber};
+use crate::screen::{PlayingState, Screen};
 use crate::AppSet;
 use bevy::reflect::{ApplyError, ReflectMut, ReflectOwned, ReflectRef, TypeInfo};
 use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_ecs_ldtk::ldtk::ldtk_fields::LdtkFields;
+use bevy_ecs_ldtk::prelude::{EntityIid, EntityInstance};
 use bevy_ecs_ldtk::GridCoords;
 use bevy_ecs_tilemap::helpers::square_grid::neighbors::SquareDirection;
 use bevy_ecs_tilemap::map::TilemapType;
 use bevy_ecs_tilemap::prelude::TilemapGridSize;
 use bevy_ecs_tilemap::tiles::TilePos;
-use leafwing_input_manager::action_state::ActionState;
+use serde::{Deserialize, Serialize};
 use std::any::Any;
+use std::str::FromStr;
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<(AutoGridPlacement, AutoFacingTurn, Facing)>();
+    app.add_systems(Update, (auto_tile_pos, apply_facing));
+    app.add_event::<CycleFinished>();
+    app.observe(apply_turn_actions);
     app.add_systems(
         Update,
-        (auto_tile_pos, handle_player_movement, apply_facing),
+        (
+            tick_active_program.in_set(AppSet::TickTimers),
+            step_active_program.in_set(AppSet::Update),
+        )
+            .run_if(in_state(PlayingState::Executing).and_then(resource_exists::<ActiveProgram>)),
     );
-    app.observe(apply_turn_actions);
+    app.add_systems(OnExit(Screen::Playing), clear_active_program);
 }
 
 #[derive(Component, Reflect, Default, Debug)]
@@ -36,7 +52,9 @@ pub struct AutoGridPlacement;
 /// Automatically face entities with this property to match their Facing property
 pub struct AutoFacingTurn;
 
-#[derive(Component, Default, Debug, Reflect, Clone, Copy)]
+#[derive(
+    Component, Default, Debug, Reflect, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
 #[reflect(Component)]
 /// Cardinal directions
 pub enum Facing {
@@ -48,6 +66,8 @@ pub enum Facing {
 }
 
 impl Facing {
+    pub const ALL: [Facing; 4] = [Facing::North, Facing::East, Facing::South, Facing::West];
+
     pub fn to_offset(&self) -> IVec2 {
         match self {
             Self::East => IVec2::new(1, 0),
@@ -74,6 +94,16 @@ impl Facing {
         }
     }
 
+    /// The rotation of a sprite drawn facing east, turned to face this way.
+    pub fn to_rotation(&self) -> Quat {
+        match self {
+            Facing::East => Quat::from_rotation_z(0.0),
+            Facing::West => Quat::from_rotation_z(std::f32::consts::PI),
+            Facing::South => Quat::from_rotation_z(-std::f32::consts::PI / 2.0),
+            Facing::North => Quat::from_rotation_z(std::f32::consts::PI / 2.0),
+        }
+    }
+
     pub fn rotate(&mut self, clockwise: bool) {
         *self = match clockwise {
             true => self.rotate_cw(),
@@ -82,92 +112,292 @@ impl Facing {
     }
 }
 
+impl FromStr for Facing {
+    type Err = String;
+
+    /// Parse the identifiers used by the `Direction` enum in LDtk.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "North" => Ok(Self::North),
+            "East" => Ok(Self::East),
+            "South" => Ok(Self::South),
+            "West" => Ok(Self::West),
+            _ => Err(format!("Unknown direction: {s}")),
+        }
+    }
+}
+
+impl From<&EntityInstance> for Facing {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        entity_instance
+            .get_enum_field("Direction")
+            .map_err(|e| e.to_string())
+            .and_then(|direction| direction.parse())
+            .unwrap_or_else(|e| {
+                warn!(
+                    "Falling back to default facing for {}: {e}",
+                    entity_instance.identifier
+                );
+                Facing::default()
+            })
+    }
+}
+
 fn auto_tile_pos(
-    mut query: Query<(&GridCoords, &mut Transform), (With<AutoGridPlacement>, Changed<GridCoords>)>,
+    mut commands: Commands,
+    settings: Res<Settings>,
+    query: Query<(Entity, &GridCoords, &Transform), (With<AutoGridPlacement>, Changed<GridCoords>)>,
 ) {
-    for (grid_coords, mut transform) in &mut query {
-        transform.translation =
-            bevy_ecs_ldtk::utils::grid_coords_to_translation(*grid_coords, GRID_SIZE_V)
-                .extend(transform.translation.z);
+    for (entity, grid_coords, transform) in &query {
+        let target = bevy_ecs_ldtk::utils::grid_coords_to_translation(*grid_coords, GRID_SIZE_V)
+            .extend(transform.translation.z);
+        commands.entity(entity).insert(TranslationTween::new(
+            transform.translation,
+            target,
+            settings.tween_duration(),
+        ));
     }
 }
 
 fn apply_facing(
-    mut query: Query<(&Facing, &mut Transform), (Changed<Facing>, With<AutoFacingTurn>)>,
+    mut commands: Commands,
+    settings: Res<Settings>,
+    query: Query<(Entity, &Facing, &Transform), (Changed<Facing>, With<AutoFacingTurn>)>,
 ) {
-    for (facing, mut transform) in &mut query {
-        match facing {
-            Facing::East => {
-                transform.rotation = Quat::from_rotation_z(0.0);
-            }
-            Facing::West => {
-                transform.rotation = Quat::from_rotation_z(std::f32::consts::PI);
-            }
-            Facing::South => {
-                transform.rotation = Quat::from_rotation_z(-std::f32::consts::PI / 2.0);
-            }
-            Facing::North => {
-                transform.rotation = Quat::from_rotation_z(std::f32::consts::PI / 2.0);
-            }
-        }
+    for (entity, facing, transform) in &query {
+        commands.entity(entity).insert(RotationTween::new(
+            transform.rotation,
+            facing.to_rotation(),
+            settings.tween_duration(),
+        ));
     }
 }
 
-fn handle_player_movement(
-    mut camera_query: Query<(&mut GridCoords, &ActionState<PlayerAction>)>,
+/// Commit a program for each listed ship. All of the programs are resolved together.
+#[derive(Event, Debug)]
+pub struct ApplyTurnActions(pub Vec<(Entity, TurnActions)>);
 
-    level_walls: Res<LevelWalls>,
-) {
-    for (mut orig_grid_coords, inputs) in &mut camera_query {
-        if let Some(action) = PlayerAction::ALL
-            .iter()
-            .find(|action| inputs.just_pressed(action))
-        {
-            let mut grid_coords = orig_grid_coords.clone();
-            match action {
-                PlayerAction::Up => {
-                    grid_coords.y += 1;
-                }
-                PlayerAction::Down => {
-                    grid_coords.y -= 1;
-                }
-                PlayerAction::Left => {
-                    grid_coords.x -= 1;
-                }
-                PlayerAction::Right => {
-                    grid_coords.x += 1;
-                }
-            }
-            if !level_walls.in_wall(&grid_coords) {
-                *orig_grid_coords = grid_coords;
-            }
-        }
-    }
+/// Triggered whenever a programmed move takes a ship to a new tile.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ShipMoved {
+    pub ship: Entity,
+    pub from: GridCoords,
+    pub to: GridCoords,
 }
 
-#[derive(Event, Debug)]
-pub struct ApplyTurnActions(pub TurnActions);
+/// Triggered whenever a programmed turn changes which way a ship is facing.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ShipRotated {
+    pub ship: Entity,
+    pub from: Facing,
+    pub to: Facing,
+}
+
+/// Triggered whenever a programmed move runs into a wall or another ship.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ShipBlocked {
+    pub ship: Entity,
+    pub at: GridCoords,
+    /// The ship that got in the way, if it wasn't a wall.
+    pub other_ship: Option<Entity>,
+    pub response: CollisionResponse,
+}
+
+/// Triggered when a ship runs out of hull. It sits out the rest of the cycle.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ShipWrecked {
+    pub ship: Entity,
+}
+
+/// Sent once every ship has played out its program for the cycle.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct CycleFinished;
+
+/// A cycle's programs being played out one step per tick.
+/// Exists for as long as the level is in [`PlayingState::Executing`].
+#[derive(Resource, Debug)]
+pub struct ActiveProgram {
+    simulation: Simulation,
+    /// `ships[i]` is the entity for ship `i` in the simulation.
+    ships: Vec<Entity>,
+    /// `docks[i]` is the entity for dock `i` in the simulation.
+    docks: Vec<Entity>,
+    timer: Timer,
+}
 
 fn apply_turn_actions(
     trigger: Trigger<ApplyTurnActions>,
-    mut player_query: Query<(&mut Facing, &mut GridCoords)>,
-    mut global_turn_lock: ResMut<GlobalTurnLock>,
+    mut commands: Commands,
+    ship_query: Query<(&ShipNumber, &Facing, &GridCoords, &Hull, &Hold), With<Player>>,
+    dock_query: Query<(Entity, &EntityIid, &Facing, &GridCoords, &DockStock), With<Dock>>,
+    settings: Res<Settings>,
+    mut next_playing_state: ResMut<NextState<PlayingState>>,
+) {
+    let programs = trigger
+        .event()
+        .0
+        .iter()
+        .filter_map(|(entity, turn_actions)| {
+            let (number, facing, coords, hull, hold) = ship_query.get(*entity).ok()?;
+            let state = ShipState::new(*facing, *coords, hull.0, *hold);
+            Some((*number, *entity, state, turn_actions.clone()))
+        })
+        .collect();
+    let (simulation, ships, docks) = new_simulation(programs, &dock_query);
+
+    commands.insert_resource(ActiveProgram {
+        simulation,
+        ships,
+        docks,
+        timer: Timer::from_seconds(settings.step_delay, TimerMode::Repeating),
+    });
+    next_playing_state.set(PlayingState::Executing);
+}
+
+/// Set up a simulation of one program per ship, alongside the entity for each simulated
+/// ship and dock. Ships are resolved in [`ShipNumber`] order, regardless of how they're listed.
+pub fn new_simulation(
+    mut programs: Vec<(ShipNumber, Entity, ShipState, TurnActions)>,
+    dock_query: &Query<(Entity, &EntityIid, &Facing, &GridCoords, &DockStock), With<Dock>>,
+) -> (Simulation, Vec<Entity>, Vec<Entity>) {
+    programs.sort_by_key(|(number, ..)| *number);
+
+    let mut ships = Vec::with_capacity(programs.len());
+    let mut states = Vec::with_capacity(programs.len());
+    let mut turn_actions = Vec::with_capacity(programs.len());
+    for (_, entity, state, actions) in programs {
+        ships.push(entity);
+        states.push(state);
+        turn_actions.push(actions);
+    }
+
+    let mut dock_list: Vec<_> = dock_query.iter().collect();
+    dock_list.sort_by_key(|(_, iid, ..)| iid.as_str());
+    let (docks, dock_states): (Vec<Entity>, Vec<DockState>) = dock_list
+        .into_iter()
+        .map(|(entity, _, facing, coords, stock)| {
+            (entity, DockState::new(*coords, *facing, *stock))
+        })
+        .unzip();
+
+    (
+        Simulation::new(states, dock_states, turn_actions),
+        ships,
+        docks,
+    )
+}
+
+fn tick_active_program(time: Res<Time>, mut active_program: ResMut<ActiveProgram>) {
+    active_program.timer.tick(time.delta());
+}
+
+fn step_active_program(
+    mut commands: Commands,
+    mut active_program: ResMut<ActiveProgram>,
+    mut ship_query: Query<(&mut Facing, &mut GridCoords, &mut Hull, &mut Hold)>,
+    mut dock_query: Query<&mut DockStock, With<Dock>>,
+    mut next_playing_state: ResMut<NextState<PlayingState>>,
     mut cycle_num: ResMut<CycleNum>,
+    mut cycle_finished: EventWriter<CycleFinished>,
     level_walls: Res<LevelWalls>,
+    level_rules: Res<LevelRules>,
 ) {
-    for (mut facing, mut coords) in player_query.iter_mut() {
-        let turn_actions = trigger.event();
-        for action in turn_actions.0.clone().0.iter() {
-            if let Some(action) = action {
-                let (new_facing, new_coords) = action.apply(&facing, &coords);
-                if !level_walls.in_wall(&new_coords) {
-                    *facing = new_facing;
-                    *coords = new_coords;
-                }
-                // TODO: Should we stop taking actions if you hit a wall, or continue?
+    if !active_program.timer.just_finished() {
+        return;
+    }
+    let active_program = &mut *active_program;
+
+    let board = Board::new(&level_walls, level_rules.collision_policy);
+    for event in active_program.simulation.step(&board) {
+        debug!("{:?}", event);
+        let ship = active_program.ships[event.ship];
+        let (at, other_ship, response) = match event.kind {
+            SimEventKind::Moved { from, to } => {
+                commands.trigger(ShipMoved { ship, from, to });
+                continue;
+            }
+            SimEventKind::Rotated { from, to } => {
+                commands.trigger(ShipRotated { ship, from, to });
+                continue;
+            }
+            SimEventKind::BlockedByWall { at, response } => (at, None, response),
+            SimEventKind::BlockedByShip {
+                at,
+                other,
+                response,
+            } => (at, Some(active_program.ships[other]), response),
+            SimEventKind::Loaded {
+                dock,
+                cargo,
+                amount,
+            } => {
+                commands.trigger(CargoTransferred {
+                    ship,
+                    dock: active_program.docks[dock],
+                    cargo,
+                    amount,
+                    direction: TransferDirection::Load,
+                });
+                continue;
+            }
+            SimEventKind::Unloaded {
+                dock,
+                cargo,
+                amount,
+            } => {
+                commands.trigger(CargoTransferred {
+                    ship,
+                    dock: active_program.docks[dock],
+                    cargo,
+                    amount,
+                    direction: TransferDirection::Unload,
+                });
+                continue;
+            }
+            SimEventKind::Wrecked => {
+                commands.trigger(ShipWrecked { ship });
+                continue;
             }
+            _ => continue,
+        };
+        commands.trigger(ShipBlocked {
+            ship,
+            at,
+            other_ship,
+            response,
+        });
+    }
+    for (entity, ship) in active_program
+        .ships
+        .iter()
+        .zip(active_program.simulation.ships())
+    {
+        if let Ok((mut facing, mut coords, mut hull, mut hold)) = ship_query.get_mut(*entity) {
+            facing.set_if_neq(ship.facing);
+            coords.set_if_neq(ship.coords);
+            hull.set_if_neq(Hull(ship.hull));
+            hold.set_if_neq(ship.hold);
+        }
+    }
+    for (entity, dock) in active_program
+        .docks
+        .iter()
+        .zip(active_program.simulation.docks())
+    {
+        if let Ok(mut stock) = dock_query.get_mut(*entity) {
+            stock.set_if_neq(dock.stock);
         }
-        global_turn_lock.unlock();
-        cycle_num.increment();
     }
+    cycle_num.increment();
+
+    if active_program.simulation.is_finished() {
+        // The objectives may end the level instead, once they've seen the cycle finish.
+        cycle_finished.send(CycleFinished);
+        next_playing_state.set(PlayingState::Planning);
+        commands.remove_resource::<ActiveProgram>();
+    }
+}
+
+fn clear_active_program(mut commands: Commands) {
+    commands.remove_resource::<ActiveProgram>();
 }
@@ -0,0 +1,184 @@
+//! Level objectives, checked at the end of every cycle.
+//!
+//! A level is won once every goal tile has a ship on it and, if the level asks for it,
+//! every contract has been completed. It is lost if a ship is wrecked, a required contract
+//! fails or expires, or the level's cycle budget runs out first.
+
+use crate::game::contracts::{check_contracts, ContractStatus, Contracts};
+use crate::game::game_ui::CycleNum;
+use crate::game::movement::{ApplyTurnActions, CycleFinished, ShipWrecked};
+use crate::game::spawn::goal::Goal;
+use crate::game::spawn::level::LevelRules;
+use crate::game::spawn::player::Player;
+use crate::screen::{PlayingState, Screen};
+use bevy::prelude::*;
+use bevy_ecs_ldtk::GridCoords;
+use serde::{Deserialize, Serialize};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<LevelStats>();
+    app.register_type::<(LevelStats, LevelResult)>();
+    app.add_systems(OnEnter(Screen::Playing), reset_level_stats);
+    app.observe(count_actions);
+    app.observe(count_wrecks);
+    app.add_systems(
+        PostUpdate,
+        evaluate_objectives
+            .after(check_contracts)
+            .run_if(in_state(Screen::Playing)),
+    );
+    app.add_systems(OnEnter(PlayingState::Won), start_level_end_delay);
+    app.add_systems(OnEnter(PlayingState::Lost), start_level_end_delay);
+    app.add_systems(
+        Update,
+        show_results.run_if(
+            in_state(PlayingState::Won)
+                .or_else(in_state(PlayingState::Lost))
+                .and_then(resource_exists::<LevelEndDelay>),
+        ),
+    );
+}
+
+/// How long to wait after a level ends before showing the results,
+/// so the last moves can be seen and heard.
+const LEVEL_END_DELAY_SECONDS: f32 = 1.0;
+
+/// How much of the player's effort the current level has taken so far.
+#[derive(Resource, Debug, Clone, Default, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct LevelStats {
+    pub actions: usize,
+    /// Ships that ran out of hull.
+    #[serde(default)]
+    pub wrecks: usize,
+}
+
+/// How the last level ended, for the results screen.
+#[derive(Resource, Debug, Clone, Default, Reflect)]
+#[reflect(Resource)]
+pub struct LevelResult {
+    pub won: bool,
+    pub reason: String,
+    pub cycles: usize,
+    pub actions: usize,
+    /// Out of [`MAX_STARS`], and 0 if the level was lost.
+    pub stars: u32,
+}
+
+pub const MAX_STARS: u32 = 3;
+
+fn reset_level_stats(mut commands: Commands) {
+    commands.insert_resource(LevelStats::default());
+}
+
+fn count_actions(trigger: Trigger<ApplyTurnActions>, mut level_stats: ResMut<LevelStats>) {
+    level_stats.actions += trigger
+        .event()
+        .0
+        .iter()
+        .map(|(_, turn_actions)| turn_actions.0.iter().flatten().count())
+        .sum::<usize>();
+}
+
+fn count_wrecks(_trigger: Trigger<ShipWrecked>, mut level_stats: ResMut<LevelStats>) {
+    level_stats.wrecks += 1;
+}
+
+/// One star for clearing the level, and one each for matching its cycle and action pars.
+fn star_rating(level_rules: &LevelRules, cycles: usize, actions: usize) -> u32 {
+    let within = |par: Option<usize>, used| par.map_or(true, |par| used <= par);
+    1 + within(level_rules.par_cycles, cycles) as u32
+        + within(level_rules.par_actions, actions) as u32
+}
+
+fn evaluate_objectives(
+    mut commands: Commands,
+    mut cycle_finished: EventReader<CycleFinished>,
+    mut next_playing_state: ResMut<NextState<PlayingState>>,
+    level_rules: Res<LevelRules>,
+    level_stats: Res<LevelStats>,
+    contracts: Res<Contracts>,
+    cycle_num: Res<CycleNum>,
+    goal_query: Query<&GridCoords, With<Goal>>,
+    ship_query: Query<&GridCoords, With<Player>>,
+) {
+    if cycle_finished.is_empty() {
+        return;
+    }
+    cycle_finished.clear();
+
+    let goals_reached = goal_query
+        .iter()
+        .all(|goal| ship_query.iter().any(|ship| ship == goal));
+    let required_contracts = if level_rules.complete_contracts {
+        contracts.0.as_slice()
+    } else {
+        &[]
+    };
+    let contracts_completed = required_contracts
+        .iter()
+        .all(|contract| contract.status == ContractStatus::Completed);
+    let contract_lost = required_contracts.iter().any(|contract| {
+        matches!(
+            contract.status,
+            ContractStatus::Failed | ContractStatus::Expired
+        )
+    });
+    let has_objectives = !goal_query.is_empty() || !required_contracts.is_empty();
+
+    let cycles = cycle_num.cycle();
+    let (won, reason) = if level_stats.wrecks > 0 {
+        (false, "A ship was wrecked")
+    } else if has_objectives && goals_reached && contracts_completed {
+        (true, "All objectives complete")
+    } else if contract_lost {
+        (false, "A contract was not fulfilled")
+    } else if level_rules
+        .cycle_budget
+        .is_some_and(|budget| cycles >= budget)
+    {
+        (false, "Ran out of cycles")
+    } else {
+        return;
+    };
+
+    let actions = level_stats.actions;
+    commands.insert_resource(LevelResult {
+        won,
+        reason: reason.to_string(),
+        cycles,
+        actions,
+        stars: if won {
+            star_rating(&level_rules, cycles, actions)
+        } else {
+            0
+        },
+    });
+    next_playing_state.set(if won {
+        PlayingState::Won
+    } else {
+        PlayingState::Lost
+    });
+}
+
+#[derive(Resource, Debug)]
+struct LevelEndDelay(Timer);
+
+fn start_level_end_delay(mut commands: Commands) {
+    commands.insert_resource(LevelEndDelay(Timer::from_seconds(
+        LEVEL_END_DELAY_SECONDS,
+        TimerMode::Once,
+    )));
+}
+
+fn show_results(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut level_end_delay: ResMut<LevelEndDelay>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    if level_end_delay.0.tick(time.delta()).finished() {
+        commands.remove_resource::<LevelEndDelay>();
+        next_screen.set(Screen::Results);
+    }
+}
@@ -0,0 +1,183 @@
+//! A ghost path on the map showing where the selected ship's program will take it.
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::game::{
+    assets::{HandleMap, ImageKey},
+    cargo::{DockStock, Hold},
+    game_ui::{CycleStore, SelectedShip},
+    movement::{new_simulation, Facing},
+    simulation::{Board, ShipState, SimEventKind},
+    spawn::{
+        dock::Dock,
+        level::{LevelRules, LevelWalls, GRID_SIZE, GRID_SIZE_V},
+        player::{Hull, Player, ShipNumber},
+    },
+};
+use crate::screen::PlayingState;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<PathMarker>();
+    app.add_systems(
+        Update,
+        update_path_preview.run_if(in_state(PlayingState::Planning)),
+    );
+}
+
+/// One step of the previewed path.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct PathMarker {
+    pub step: usize,
+    /// The ship would run into a wall or another ship on this step.
+    pub blocked: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PreviewStep {
+    step: usize,
+    coords: GridCoords,
+    facing: Facing,
+    blocked: bool,
+}
+
+const MARKER_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.5);
+const BLOCKED_MARKER_COLOR: Color = Color::srgba(1.0, 0.2, 0.2, 0.8);
+
+/// Between the tiles and the ships.
+const MARKER_Z: f32 = 1.5;
+
+fn update_path_preview(
+    mut commands: Commands,
+    selected_ship: Res<SelectedShip>,
+    ship_query: Query<
+        (
+            Entity,
+            &ShipNumber,
+            &Facing,
+            &GridCoords,
+            &Hull,
+            &Hold,
+            &CycleStore,
+        ),
+        With<Player>,
+    >,
+    dock_query: Query<(Entity, &EntityIid, &Facing, &GridCoords, &DockStock), With<Dock>>,
+    level_walls: Res<LevelWalls>,
+    level_rules: Res<LevelRules>,
+    marker_query: Query<Entity, With<PathMarker>>,
+    image_handles: Res<HandleMap<ImageKey>>,
+    mut last_preview: Local<Vec<PreviewStep>>,
+) {
+    let preview = selected_ship
+        .0
+        .map(|ship| predict_path(ship, &ship_query, &dock_query, &level_walls, &level_rules))
+        .unwrap_or_default();
+    // Markers are despawned whenever planning ends, so a matching count means they're current.
+    if preview == *last_preview && marker_query.iter().count() == preview.len() {
+        return;
+    }
+
+    for entity in &marker_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    *last_preview = preview;
+    let Some(texture) = image_handles.get(&ImageKey::OverlayMarker) else {
+        return;
+    };
+    // Levels sit at the origin, so grid coordinates translate straight to world space.
+    for step in last_preview.iter() {
+        let translation =
+            bevy_ecs_ldtk::utils::grid_coords_to_translation(step.coords, GRID_SIZE_V)
+                .extend(MARKER_Z);
+        commands.spawn((
+            Name::new(format!("PathMarker{}", step.step)),
+            PathMarker {
+                step: step.step,
+                blocked: step.blocked,
+            },
+            SpriteBundle {
+                texture: texture.clone_weak(),
+                sprite: Sprite {
+                    color: if step.blocked {
+                        BLOCKED_MARKER_COLOR
+                    } else {
+                        MARKER_COLOR
+                    },
+                    custom_size: Some(Vec2::splat(GRID_SIZE as f32)),
+                    ..default()
+                },
+                transform: Transform::from_translation(translation)
+                    .with_rotation(step.facing.to_rotation()),
+                ..default()
+            },
+            StateScoped(PlayingState::Planning),
+        ));
+    }
+}
+
+/// Run every ship's program as it stands, and record where `ship` ends up after each action.
+/// Other ships' programs are included so that collisions between them show up too.
+fn predict_path(
+    ship: Entity,
+    ship_query: &Query<
+        (
+            Entity,
+            &ShipNumber,
+            &Facing,
+            &GridCoords,
+            &Hull,
+            &Hold,
+            &CycleStore,
+        ),
+        With<Player>,
+    >,
+    dock_query: &Query<(Entity, &EntityIid, &Facing, &GridCoords, &DockStock), With<Dock>>,
+    level_walls: &LevelWalls,
+    level_rules: &LevelRules,
+) -> Vec<PreviewStep> {
+    let programs = ship_query
+        .iter()
+        .map(
+            |(entity, number, facing, coords, hull, hold, cycle_store)| {
+                let state = ShipState::new(*facing, *coords, hull.0, *hold);
+                (*number, entity, state, cycle_store.turn_actions().clone())
+            },
+        )
+        .collect();
+    let (mut simulation, ships, _) = new_simulation(programs, dock_query);
+    let Some(ship_idx) = ships.iter().position(|entity| *entity == ship) else {
+        return Vec::new();
+    };
+    let Ok((.., cycle_store)) = ship_query.get(ship) else {
+        return Vec::new();
+    };
+    let program = &cycle_store.turn_actions().0;
+
+    let board = Board::new(level_walls, level_rules.collision_policy);
+    let mut preview = Vec::new();
+    for (step, action) in program.iter().enumerate() {
+        let events = simulation.step(&board);
+        if action.is_none() {
+            continue;
+        }
+        let state = simulation.ships()[ship_idx];
+        let blocked_at = events
+            .iter()
+            .filter(|event| event.ship == ship_idx)
+            .find_map(|event| match event.kind {
+                SimEventKind::BlockedByWall { at, .. } | SimEventKind::BlockedByShip { at, .. } => {
+                    Some(at)
+                }
+                _ => None,
+            });
+        preview.push(PreviewStep {
+            step,
+            coords: blocked_at.unwrap_or(state.coords),
+            facing: state.facing,
+            blocked: blocked_at.is_some(),
+        });
+    }
+    preview
+}
@@ -0,0 +1,343 @@
+//! Recordings of level sessions, and playing them back without a Bevy `World`.
+//!
+//! Every program the player commits is recorded along with the level and the [`GameRng`] seed,
+//! which is all that's needed to run the session again through the
+//! [simulation](crate::game::simulation). A checksum of the final ships and docks is kept too,
+//! so playback can tell whether it ended up in the same place. Replays are only saved to disk
+//! on native.
+
+use crate::game::cargo::{DockStock, Hold};
+use crate::game::game_ui::{CycleNum, TurnActions};
+use crate::game::level_check::LevelLayout;
+use crate::game::movement::{ApplyTurnActions, Facing};
+use crate::game::rng::GameRng;
+use crate::game::save::{seconds_since_epoch, PendingSnapshot};
+use crate::game::simulation::{Board, DockState, ShipState, Simulation};
+use crate::game::spawn::dock::Dock;
+use crate::game::spawn::harbour::PracticeHarbour;
+use crate::game::spawn::player::{Hull, Player};
+use crate::screen::{PlayingState, Screen};
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::Playing), start_recording);
+    app.observe(record_turn_actions);
+    app.add_systems(
+        OnEnter(PlayingState::Won),
+        finish_recording.run_if(resource_exists::<ReplayRecorder>),
+    );
+    app.add_systems(
+        OnEnter(PlayingState::Lost),
+        finish_recording.run_if(resource_exists::<ReplayRecorder>),
+    );
+}
+
+#[cfg(not(target_family = "wasm"))]
+const REPLAY_DIR: &str = "replays";
+
+/// How many replays are kept on disk. The oldest are removed as new ones are recorded.
+#[cfg(not(target_family = "wasm"))]
+const MAX_REPLAYS: usize = 20;
+
+/// Which level a replay was recorded on.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplayLevel {
+    /// A campaign level, by its LDtk iid.
+    Campaign(String),
+    /// A generated harbour, by the seed it was generated from.
+    Harbour(u64),
+}
+
+/// The programs committed at the start of one cycle.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedCycle {
+    pub cycle: usize,
+    /// Each ship's program, by the ship's iid.
+    pub programs: Vec<(String, TurnActions)>,
+}
+
+/// Everything needed to play a session of a level again.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub level: ReplayLevel,
+    /// The [`GameRng`] seed the level was played with.
+    pub seed: u64,
+    /// Seconds since the Unix epoch.
+    pub recorded_at: u64,
+    pub cycles: Vec<RecordedCycle>,
+    /// The [`state_checksum`] of the level once it had ended.
+    pub checksum: u64,
+}
+
+/// The replay of the most recently finished level.
+#[derive(Resource, Debug, Clone)]
+pub struct LastReplay(pub Replay);
+
+/// The programs committed so far in the level being played.
+/// Missing if the level can't be replayed.
+#[derive(Resource, Debug, Default)]
+struct ReplayRecorder(Vec<RecordedCycle>);
+
+fn start_recording(mut commands: Commands, pending_snapshot: Option<Res<PendingSnapshot>>) {
+    // A level restored from a save doesn't start where its level does, so it can't be replayed.
+    if pending_snapshot.is_some() {
+        commands.remove_resource::<ReplayRecorder>();
+    } else {
+        commands.insert_resource(ReplayRecorder::default());
+    }
+}
+
+fn record_turn_actions(
+    trigger: Trigger<ApplyTurnActions>,
+    recorder: Option<ResMut<ReplayRecorder>>,
+    iid_query: Query<&EntityIid>,
+    cycle_num: Res<CycleNum>,
+) {
+    let Some(mut recorder) = recorder else {
+        return;
+    };
+    let programs = trigger
+        .event()
+        .0
+        .iter()
+        .filter_map(|(entity, turn_actions)| {
+            let iid = iid_query.get(*entity).ok()?;
+            Some((iid.as_str().to_string(), turn_actions.clone()))
+        })
+        .collect();
+    recorder.0.push(RecordedCycle {
+        cycle: cycle_num.cycle(),
+        programs,
+    });
+}
+
+fn finish_recording(
+    mut commands: Commands,
+    recorder: Res<ReplayRecorder>,
+    game_rng: Res<GameRng>,
+    practice_harbour: Option<Res<PracticeHarbour>>,
+    level_query: Query<&LevelIid>,
+    ship_query: Query<(&EntityIid, &Facing, &GridCoords, &Hull, &Hold), With<Player>>,
+    dock_query: Query<(&EntityIid, &DockStock), With<Dock>>,
+) {
+    let level = match (practice_harbour, level_query.get_single()) {
+        (Some(practice_harbour), _) => ReplayLevel::Harbour(practice_harbour.seed),
+        (None, Ok(level_iid)) => ReplayLevel::Campaign(level_iid.get().clone()),
+        (None, Err(_)) => return,
+    };
+
+    // Both sorted by iid, the order the simulation uses.
+    let mut ships: Vec<_> = ship_query
+        .iter()
+        .map(|(iid, facing, coords, hull, hold)| {
+            (
+                iid.as_str(),
+                ShipState::new(*facing, *coords, hull.0, *hold),
+            )
+        })
+        .collect();
+    ships.sort_by_key(|(iid, _)| *iid);
+    let mut docks: Vec<_> = dock_query
+        .iter()
+        .map(|(iid, stock)| (iid.as_str(), stock))
+        .collect();
+    docks.sort_by_key(|(iid, _)| *iid);
+
+    let replay = Replay {
+        level,
+        seed: game_rng.seed(),
+        recorded_at: seconds_since_epoch(),
+        cycles: recorder.0.clone(),
+        checksum: state_checksum(
+            ships.iter().map(|(_, ship)| ship),
+            docks.iter().map(|(_, stock)| *stock),
+        ),
+    };
+    if let Err(e) = write_replay(&replay) {
+        warn!("Failed to save replay: {e}");
+    }
+    commands.insert_resource(LastReplay(replay));
+    commands.remove_resource::<ReplayRecorder>();
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A checksum of every ship and what each dock has in stock, listed in simulation order.
+/// This is FNV-1a over the serialized state, so it doesn't change between platforms or builds.
+pub fn state_checksum<'a>(
+    ships: impl IntoIterator<Item = &'a ShipState>,
+    docks: impl IntoIterator<Item = &'a DockStock>,
+) -> u64 {
+    let ships: Vec<_> = ships
+        .into_iter()
+        .map(|ship| {
+            (
+                ship.facing,
+                (ship.coords.x, ship.coords.y),
+                ship.hull,
+                ship.hold,
+            )
+        })
+        .collect();
+    let docks: Vec<_> = docks.into_iter().collect();
+    let state = ron::to_string(&(ships, docks)).unwrap_or_default();
+    state.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Where everything was after one step of a replay.
+#[derive(Clone, Debug)]
+pub struct ReplayFrame {
+    pub cycle: usize,
+    /// The step of the cycle's program that was just resolved, or `None` before the first.
+    pub step: Option<usize>,
+    /// In [`ShipNumber`] order.
+    ///
+    /// [`ShipNumber`]: crate::game::spawn::player::ShipNumber
+    pub ships: Vec<ShipState>,
+    pub docks: Vec<DockState>,
+}
+
+/// A replay played through from start to finish.
+#[derive(Clone, Debug)]
+pub struct ReplayRun {
+    pub layout: LevelLayout,
+    /// One frame for the start, then one for every step.
+    pub frames: Vec<ReplayFrame>,
+    /// Whether the session ended up the same as when it was recorded.
+    pub checksum_matches: bool,
+}
+
+/// Run each recorded cycle through the simulation, starting from `layout`.
+pub fn run_replay(replay: &Replay, layout: LevelLayout) -> Result<ReplayRun, String> {
+    let board = Board::new(&layout.walls, layout.rules.collision_policy);
+    let mut ships: Vec<ShipState> = layout
+        .ships
+        .iter()
+        .map(|(_, facing, coords)| {
+            ShipState::new(*facing, *coords, Hull::default().0, Hold::default())
+        })
+        .collect();
+    let mut docks: Vec<DockState> = layout.docks.iter().map(|(.., dock)| *dock).collect();
+
+    let mut frames = vec![ReplayFrame {
+        cycle: 0,
+        step: None,
+        ships: ships.clone(),
+        docks: docks.clone(),
+    }];
+    for recorded in &replay.cycles {
+        // Like in the game, only the ships with a program take part, in ship number order.
+        let mut programs = recorded
+            .programs
+            .iter()
+            .map(|(iid, turn_actions)| {
+                let number = layout
+                    .ships
+                    .iter()
+                    .position(|(ship_iid, ..)| ship_iid == iid)
+                    .ok_or_else(|| format!("The level has no ship {iid}"))?;
+                Ok((number, turn_actions.clone()))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        programs.sort_by_key(|(number, _)| *number);
+
+        let (numbers, turn_actions): (Vec<usize>, Vec<TurnActions>) = programs.into_iter().unzip();
+        let mut simulation = Simulation::new(
+            numbers.iter().map(|number| ships[*number]).collect(),
+            docks.clone(),
+            turn_actions,
+        );
+        while !simulation.is_finished() {
+            let step = simulation.current_step();
+            simulation.step(&board);
+            for (number, ship) in numbers.iter().zip(simulation.ships()) {
+                ships[*number] = *ship;
+            }
+            docks = simulation.docks().to_vec();
+            frames.push(ReplayFrame {
+                cycle: recorded.cycle,
+                step: Some(step),
+                ships: ships.clone(),
+                docks: docks.clone(),
+            });
+        }
+    }
+
+    let checksum = state_checksum(&ships, docks.iter().map(|dock| &dock.stock));
+    Ok(ReplayRun {
+        layout,
+        frames,
+        checksum_matches: checksum == replay.checksum,
+    })
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn replay_dir() -> Option<std::path::PathBuf> {
+    crate::game::save::data_dir().map(|dir| dir.join(REPLAY_DIR))
+}
+
+/// Every replay on disk with when it was recorded, oldest first.
+#[cfg(not(target_family = "wasm"))]
+fn replay_files() -> Vec<(u64, std::path::PathBuf)> {
+    let Some(entries) = replay_dir().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut files: Vec<_> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let recorded_at: u64 = path
+                .file_name()?
+                .to_str()?
+                .strip_prefix("replay_")?
+                .strip_suffix(".ron")?
+                .parse()
+                .ok()?;
+            Some((recorded_at, path))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// The most recently recorded replay on disk.
+#[cfg(not(target_family = "wasm"))]
+pub fn latest_replay() -> Option<Replay> {
+    let (_, latest) = replay_files().pop()?;
+    let contents = std::fs::read_to_string(latest).ok()?;
+    ron::from_str(&contents)
+        .inspect_err(|e| warn!("Ignoring unreadable replay: {e}"))
+        .ok()
+}
+
+#[cfg(target_family = "wasm")]
+pub fn latest_replay() -> Option<Replay> {
+    None
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn write_replay(replay: &Replay) -> Result<(), String> {
+    // Not pretty-printed, since nobody should need to read these by hand.
+    let contents = ron::to_string(replay).map_err(|e| e.to_string())?;
+    let dir = replay_dir().ok_or("no data directory")?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("replay_{}.ron", replay.recorded_at));
+    std::fs::write(path, contents).map_err(|e| e.to_string())?;
+
+    let files = replay_files();
+    for (_, old) in &files[..files.len().saturating_sub(MAX_REPLAYS)] {
+        if let Err(e) = std::fs::remove_file(old) {
+            warn!("Failed to remove old replay {}: {e}", old.display());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_family = "wasm")]
+fn write_replay(_replay: &Replay) -> Result<(), String> {
+    Err("saving replays is not supported on the web".to_string())
+}
@@ -0,0 +1,94 @@
+//! The game's only source of randomness.
+//!
+//! [`GameRng`] is seeded from entropy once at startup, and reseeded whenever a level is
+//! spawned, so the seed and the programs the player ran are enough to play a session back
+//! exactly. Campaign levels are always reseeded with their [`level_seed`], however they were
+//! reached. Gameplay code, including picking the seed for the next harbour, must draw from [`RngStream::Gameplay`] rather than `rand::thread_rng()`. Sounds and visual
+//! flourishes get streams of their own, so that muting the game or skipping an effect can't
+//! change what happens in it.
+
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<GameRng>();
+}
+
+/// Independent sequences of random numbers drawn from the same seed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RngStream {
+    /// Anything that affects the outcome of a level.
+    Gameplay,
+    Audio,
+    /// Effects that are only for show.
+    Cosmetic,
+}
+
+impl RngStream {
+    pub const ALL: [RngStream; 3] = [RngStream::Gameplay, RngStream::Audio, RngStream::Cosmetic];
+}
+
+/// Seeded random numbers for the level being played.
+///
+/// ChaCha is used rather than `StdRng` because its output is guaranteed not to change
+/// between versions of `rand`, which seeds shared between players rely on.
+#[derive(Resource, Debug, Clone)]
+pub struct GameRng {
+    seed: u64,
+    streams: [ChaCha8Rng; RngStream::ALL.len()],
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            streams: RngStream::ALL.map(|stream| stream_rng(seed, stream as u64)),
+        }
+    }
+
+    /// The seed the level was started with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn stream(&mut self, stream: RngStream) -> &mut ChaCha8Rng {
+        &mut self.streams[stream as usize]
+    }
+
+    /// A new generator seeded from `stream`, for work that draws a varying amount of
+    /// numbers, so that it doesn't shift everything drawn from `stream` after it.
+    pub fn fork(&mut self, stream: RngStream) -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(self.stream(stream).gen())
+    }
+
+    /// A seed for the next harbour.
+    pub fn next_seed(&mut self) -> u64 {
+        self.stream(RngStream::Gameplay).gen()
+    }
+}
+
+/// Mixed into every level's seed, so that levels aren't seeded like the practice harbours.
+const LEVEL_SEED_SALT: u64 = 0x4c45_5645_4c53_4545;
+
+/// The seed a campaign level is played with, whatever was played before it.
+/// This is FNV-1a rather than `DefaultHasher`, whose output may change between versions.
+pub fn level_seed(level_iid: &str) -> u64 {
+    level_iid
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325 ^ LEVEL_SEED_SALT, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+fn stream_rng(seed: u64, stream: u64) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(stream);
+    rng
+}
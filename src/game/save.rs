@@ -0,0 +1,389 @@
+//! Snapshots of a level in progress, saved to slots on disk.
+//!
+//! Entities are stored by their LDtk iids, so a snapshot can be applied to a freshly
+//! spawned copy of the same level. Generated harbours have no iids, so they can't be saved.
+//! Saving to disk is only supported on native.
+
+use crate::game::campaign::CampaignLevel;
+use crate::game::cargo::{CargoType, DockStock, Hold};
+use crate::game::contracts::{CargoOrigin, Contract, ContractStatus, Contracts};
+use crate::game::game_ui::{CycleNum, CycleStore, SelectedShip, StatusMessage};
+use crate::game::movement::{ActiveProgram, Facing};
+use crate::game::objectives::LevelStats;
+use crate::game::rng::GameRng;
+use crate::game::spawn::dock::Dock;
+use crate::game::spawn::player::{Hull, Player};
+use crate::screen::Screen;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_ecs_ldtk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+pub(super) fn plugin(app: &mut App) {
+    app.observe(save_game);
+    app.add_systems(OnEnter(Screen::Results), clear_autosave);
+    // Wait until the level has spawned and `load_contracts` has run before overwriting it.
+    app.add_systems(
+        PostUpdate,
+        apply_pending_snapshot
+            .run_if(in_state(Screen::Playing).and_then(resource_exists::<PendingSnapshot>)),
+    );
+}
+
+/// How many slots the player can save to, not counting the autosave.
+pub const SAVE_SLOTS: usize = 3;
+
+#[cfg(not(target_family = "wasm"))]
+const SAVE_DIR: &str = "saves";
+
+/// Where everything the game writes while it's played is kept, or `None` if the platform
+/// doesn't have a data directory.
+#[cfg(not(target_family = "wasm"))]
+pub fn data_dir() -> Option<std::path::PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("shipping-friends"))
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn save_dir() -> Option<std::path::PathBuf> {
+    data_dir().map(|dir| dir.join(SAVE_DIR))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub enum SaveSlot {
+    /// Written whenever the player leaves a level part way through.
+    Auto,
+    Manual(usize),
+}
+
+impl SaveSlot {
+    pub fn all() -> impl Iterator<Item = SaveSlot> {
+        std::iter::once(SaveSlot::Auto).chain((1..=SAVE_SLOTS).map(SaveSlot::Manual))
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    fn path(&self) -> Option<std::path::PathBuf> {
+        let file_name = match self {
+            SaveSlot::Auto => "autosave.ron".to_string(),
+            SaveSlot::Manual(slot) => format!("slot_{slot}.ron"),
+        };
+        save_dir().map(|dir| dir.join(file_name))
+    }
+}
+
+impl Display for SaveSlot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveSlot::Auto => write!(f, "Autosave"),
+            SaveSlot::Manual(slot) => write!(f, "Slot {slot}"),
+        }
+    }
+}
+
+/// Everything needed to put a level back exactly as the player left it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LevelSnapshot {
+    pub level_iid: String,
+    /// Seconds since the Unix epoch, used to find the most recent save.
+    pub saved_at: u64,
+    /// The [`GameRng`] seed the level was started with.
+    pub seed: u64,
+    pub cycle_num: CycleNum,
+    pub level_stats: LevelStats,
+    pub selected_ship: Option<String>,
+    pub ships: Vec<ShipSnapshot>,
+    pub docks: Vec<DockSnapshot>,
+    pub contracts: Vec<ContractSnapshot>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ShipSnapshot {
+    pub iid: String,
+    pub coords: (i32, i32),
+    pub facing: Facing,
+    pub hull: u32,
+    pub hold: Hold,
+    pub cargo_origin: Option<String>,
+    /// The ship's program and remaining actions, as they were in the editor.
+    pub cycle_store: CycleStore,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DockSnapshot {
+    pub iid: String,
+    pub stock: DockStock,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ContractSnapshot {
+    pub cargo: CargoType,
+    pub amount: u32,
+    pub delivered: u32,
+    pub from: String,
+    pub to: String,
+    pub deadline: usize,
+    pub status: ContractStatus,
+}
+
+/// A snapshot to apply as soon as its level has spawned.
+#[derive(Resource, Debug)]
+pub struct PendingSnapshot(pub LevelSnapshot);
+
+/// Triggered to save the level in progress to a slot.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SaveGame(pub SaveSlot);
+
+fn save_game(
+    trigger: Trigger<SaveGame>,
+    active_program: Option<Res<ActiveProgram>>,
+    level_query: Query<&LevelIid>,
+    ship_query: Query<
+        (
+            &EntityIid,
+            &GridCoords,
+            &Facing,
+            &Hull,
+            &Hold,
+            &CargoOrigin,
+            &CycleStore,
+        ),
+        With<Player>,
+    >,
+    dock_query: Query<(&EntityIid, &DockStock), With<Dock>>,
+    iid_query: Query<&EntityIid>,
+    cycle_num: Res<CycleNum>,
+    level_stats: Res<LevelStats>,
+    contracts: Res<Contracts>,
+    selected_ship: Res<SelectedShip>,
+    game_rng: Res<GameRng>,
+    mut status_message: ResMut<StatusMessage>,
+) {
+    let slot = trigger.event().0;
+    // A program part way through has already been taken out of the editor, so it can't be saved.
+    if active_program.is_some() {
+        status_message.0 = Some("Can't save while ships are moving".to_string());
+        return;
+    }
+    let Ok(level_iid) = level_query.get_single() else {
+        status_message.0 = Some("Harbours can't be saved".to_string());
+        return;
+    };
+    let iid_of = |entity: Entity| {
+        iid_query
+            .get(entity)
+            .map(|iid| iid.as_str().to_string())
+            .ok()
+    };
+
+    let snapshot = LevelSnapshot {
+        level_iid: level_iid.get().clone(),
+        saved_at: seconds_since_epoch(),
+        seed: game_rng.seed(),
+        cycle_num: cycle_num.clone(),
+        level_stats: level_stats.clone(),
+        selected_ship: selected_ship.0.and_then(iid_of),
+        ships: ship_query
+            .iter()
+            .map(
+                |(iid, coords, facing, hull, hold, cargo_origin, cycle_store)| ShipSnapshot {
+                    iid: iid.as_str().to_string(),
+                    coords: (coords.x, coords.y),
+                    facing: *facing,
+                    hull: hull.0,
+                    hold: *hold,
+                    cargo_origin: cargo_origin.0.and_then(iid_of),
+                    cycle_store: cycle_store.clone(),
+                },
+            )
+            .collect(),
+        docks: dock_query
+            .iter()
+            .map(|(iid, stock)| DockSnapshot {
+                iid: iid.as_str().to_string(),
+                stock: *stock,
+            })
+            .collect(),
+        contracts: contracts
+            .0
+            .iter()
+            .filter_map(|contract| {
+                Some(ContractSnapshot {
+                    cargo: contract.cargo,
+                    amount: contract.amount,
+                    delivered: contract.delivered,
+                    from: iid_of(contract.from)?,
+                    to: iid_of(contract.to)?,
+                    deadline: contract.deadline,
+                    status: contract.status,
+                })
+            })
+            .collect(),
+    };
+
+    status_message.0 = Some(match write_snapshot(slot, &snapshot) {
+        Ok(()) => format!("Saved to {slot}"),
+        Err(e) => {
+            warn!("Failed to save to {slot}: {e}");
+            format!("Failed to save to {slot}")
+        }
+    });
+}
+
+fn apply_pending_snapshot(
+    mut commands: Commands,
+    mut level_events: EventReader<LevelEvent>,
+    pending_snapshot: Res<PendingSnapshot>,
+    iid_query: Query<(Entity, &EntityIid)>,
+    mut ship_query: Query<
+        (
+            &EntityIid,
+            &mut GridCoords,
+            &mut Facing,
+            &mut Hull,
+            &mut Hold,
+            &mut CargoOrigin,
+            &mut CycleStore,
+        ),
+        With<Player>,
+    >,
+    mut dock_query: Query<(&EntityIid, &mut DockStock), With<Dock>>,
+    mut cycle_num: ResMut<CycleNum>,
+    mut level_stats: ResMut<LevelStats>,
+    mut contracts: ResMut<Contracts>,
+    mut selected_ship: ResMut<SelectedShip>,
+) {
+    let spawned = level_events
+        .read()
+        .any(|level_event| matches!(level_event, LevelEvent::Spawned(_)));
+    if !spawned {
+        return;
+    }
+    let snapshot = &pending_snapshot.0;
+    let entities: HashMap<&str, Entity> = iid_query
+        .iter()
+        .map(|(entity, iid)| (iid.as_str(), entity))
+        .collect();
+    let entity_of = |iid: &str| entities.get(iid).copied();
+
+    for (iid, mut coords, mut facing, mut hull, mut hold, mut cargo_origin, mut cycle_store) in
+        &mut ship_query
+    {
+        let Some(ship) = snapshot.ships.iter().find(|ship| ship.iid == iid.as_str()) else {
+            continue;
+        };
+        *coords = GridCoords::new(ship.coords.0, ship.coords.1);
+        *facing = ship.facing;
+        *hull = Hull(ship.hull);
+        *hold = ship.hold;
+        *cargo_origin = CargoOrigin(ship.cargo_origin.as_deref().and_then(entity_of));
+        *cycle_store = ship.cycle_store.clone();
+    }
+    for (iid, mut stock) in &mut dock_query {
+        if let Some(dock) = snapshot.docks.iter().find(|dock| dock.iid == iid.as_str()) {
+            *stock = dock.stock;
+        }
+    }
+
+    *cycle_num = snapshot.cycle_num.clone();
+    *level_stats = snapshot.level_stats.clone();
+    *contracts = Contracts(
+        snapshot
+            .contracts
+            .iter()
+            .filter_map(|contract| {
+                Some(Contract {
+                    cargo: contract.cargo,
+                    amount: contract.amount,
+                    delivered: contract.delivered,
+                    from: entity_of(&contract.from)?,
+                    to: entity_of(&contract.to)?,
+                    deadline: contract.deadline,
+                    status: contract.status,
+                })
+            })
+            .collect(),
+    );
+    if let Some(ship) = snapshot.selected_ship.as_deref().and_then(entity_of) {
+        selected_ship.0 = Some(ship);
+    }
+
+    commands.remove_resource::<PendingSnapshot>();
+}
+
+/// Select the snapshot's level and queue the snapshot to be applied once it spawns.
+/// Returns `false` if the level is no longer in the campaign.
+pub fn restore_snapshot(
+    commands: &mut Commands,
+    level_selection: &mut LevelSelection,
+    levels: &[CampaignLevel],
+    snapshot: LevelSnapshot,
+) -> bool {
+    let Some(index) = levels
+        .iter()
+        .position(|level| level.iid == snapshot.level_iid)
+    else {
+        warn!("Saved level {} no longer exists", snapshot.level_iid);
+        return false;
+    };
+    *level_selection = LevelSelection::index(index);
+    commands.insert_resource(PendingSnapshot(snapshot));
+    true
+}
+
+/// The most recently written save, if there is one.
+pub fn latest_snapshot() -> Option<(SaveSlot, LevelSnapshot)> {
+    SaveSlot::all()
+        .filter_map(|slot| read_snapshot(slot).map(|snapshot| (slot, snapshot)))
+        .max_by_key(|(_, snapshot)| snapshot.saved_at)
+}
+
+#[cfg(not(target_family = "wasm"))]
+pub fn read_snapshot(slot: SaveSlot) -> Option<LevelSnapshot> {
+    let contents = std::fs::read_to_string(slot.path()?).ok()?;
+    ron::from_str(&contents)
+        .inspect_err(|e| warn!("Ignoring unreadable save in {slot}: {e}"))
+        .ok()
+}
+
+#[cfg(target_family = "wasm")]
+pub fn read_snapshot(_slot: SaveSlot) -> Option<LevelSnapshot> {
+    None
+}
+
+/// A finished level can't be continued, so don't offer to.
+#[cfg(not(target_family = "wasm"))]
+fn clear_autosave() {
+    let Some(path) = SaveSlot::Auto.path() else {
+        return;
+    };
+    if path.exists() {
+        if let Err(e) = std::fs::remove_file(path) {
+            warn!("Failed to remove autosave: {e}");
+        }
+    }
+}
+
+#[cfg(target_family = "wasm")]
+fn clear_autosave() {}
+
+#[cfg(not(target_family = "wasm"))]
+fn write_snapshot(slot: SaveSlot, snapshot: &LevelSnapshot) -> Result<(), String> {
+    let contents = ron::ser::to_string_pretty(snapshot, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())?;
+    let path = slot.path().ok_or("no data directory")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, contents).map_err(|e| e.to_string())
+}
+
+#[cfg(target_family = "wasm")]
+fn write_snapshot(_slot: SaveSlot, _snapshot: &LevelSnapshot) -> Result<(), String> {
+    Err("saving is not supported on the web".to_string())
+}
+
+pub fn seconds_since_epoch() -> u64 {
+    bevy::utils::SystemTime::now()
+        .duration_since(bevy::utils::SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
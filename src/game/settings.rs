@@ -1,16 +1,245 @@
-use bevy::prelude::Resource;
+//! Player settings, stored in a config file and applied to the primary window.
 
-#[derive(Debug, Clone, Resource)]
+use crate::game::audio::AudioChannel;
+use bevy::prelude::*;
+use bevy::window::{PresentMode, PrimaryWindow};
+use bevy_egui::{EguiContext, EguiSettings};
+use serde::{Deserialize, Serialize};
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(Settings::load());
+    app.add_systems(
+        Update,
+        (
+            apply_window_settings.run_if(resource_changed::<Settings>),
+            save_settings,
+        ),
+    );
+}
+
+#[derive(Debug, Clone, PartialEq, Resource, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Settings {
+    pub master_volume: ChannelVolume,
+    pub music_volume: ChannelVolume,
+    pub sfx_volume: ChannelVolume,
+    pub ui_volume: ChannelVolume,
+    /// Seconds between each step of a program while it is being executed.
+    pub step_delay: f32,
+    pub display_mode: DisplayMode,
+    /// Window size in logical pixels, used when [`DisplayMode::Windowed`].
+    pub resolution: (u32, u32),
+    pub vsync: bool,
+    /// Scale applied to both the bevy and egui UI.
+    pub ui_scale: f32,
+}
+
+impl Settings {
+    /// How long a ship takes to move or turn between tiles.
+    /// Slightly shorter than the step delay so each step settles before the next one.
+    pub fn tween_duration(&self) -> f32 {
+        self.step_delay * 0.8
+    }
+
+    pub fn channel(&self, channel: AudioChannel) -> &ChannelVolume {
+        match channel {
+            AudioChannel::Master => &self.master_volume,
+            AudioChannel::Music => &self.music_volume,
+            AudioChannel::Sfx => &self.sfx_volume,
+            AudioChannel::Ui => &self.ui_volume,
+        }
+    }
+
+    pub fn channel_mut(&mut self, channel: AudioChannel) -> &mut ChannelVolume {
+        match channel {
+            AudioChannel::Master => &mut self.master_volume,
+            AudioChannel::Music => &mut self.music_volume,
+            AudioChannel::Sfx => &mut self.sfx_volume,
+            AudioChannel::Ui => &mut self.ui_volume,
+        }
+    }
+
+    /// The volume to play a sound on this channel at, including the master volume.
+    pub fn volume(&self, channel: AudioChannel) -> f32 {
+        let master = self.master_volume.effective();
+        match channel {
+            AudioChannel::Master => master,
+            _ => master * self.channel(channel).effective(),
+        }
+    }
+}
+
+/// The volume of one [`AudioChannel`], and whether it has been muted.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ChannelVolume {
     pub volume: f32,
-    pub music: bool,
+    pub muted: bool,
+}
+
+impl ChannelVolume {
+    fn new(volume: f32) -> Self {
+        Self {
+            volume,
+            muted: false,
+        }
+    }
+
+    pub fn effective(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.volume
+        }
+    }
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Self {
-            volume: 0.5,
-            music: false,
+            master_volume: ChannelVolume::new(0.5),
+            music_volume: ChannelVolume {
+                volume: 1.0,
+                muted: true,
+            },
+            sfx_volume: ChannelVolume::new(1.0),
+            ui_volume: ChannelVolume::new(1.0),
+            step_delay: 0.5,
+            display_mode: DisplayMode::Windowed,
+            resolution: (1280, 720),
+            vsync: true,
+            ui_scale: 1.0,
+        }
+    }
+}
+
+/// The window sizes offered in the settings screen.
+pub const RESOLUTIONS: [(u32, u32); 4] = [(1280, 720), (1600, 900), (1920, 1080), (2560, 1440)];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    Windowed,
+    BorderlessFullscreen,
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub const ALL: [DisplayMode; 3] = [
+        DisplayMode::Windowed,
+        DisplayMode::BorderlessFullscreen,
+        DisplayMode::Fullscreen,
+    ];
+
+    #[cfg(not(target_family = "wasm"))]
+    fn window_mode(&self) -> bevy::window::WindowMode {
+        use bevy::window::WindowMode;
+        match self {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::BorderlessFullscreen => WindowMode::BorderlessFullscreen,
+            DisplayMode::Fullscreen => WindowMode::Fullscreen,
+        }
+    }
+}
+
+impl std::fmt::Display for DisplayMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DisplayMode::Windowed => write!(f, "Windowed"),
+            DisplayMode::BorderlessFullscreen => write!(f, "Borderless"),
+            DisplayMode::Fullscreen => write!(f, "Fullscreen"),
+        }
+    }
+}
+
+fn apply_window_settings(
+    settings: Res<Settings>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    mut ui_scale: ResMut<UiScale>,
+    mut egui_settings: ResMut<EguiSettings>,
+    #[cfg(not(target_family = "wasm"))] mut applied_mode: Local<Option<(DisplayMode, (u32, u32))>>,
+) {
+    let Ok(mut window) = window_query.get_single_mut() else {
+        return;
+    };
+    // The web build fills its canvas, so only the native window can be resized.
+    // Only touch the size when it was changed here, so resizing the window by hand sticks.
+    #[cfg(not(target_family = "wasm"))]
+    if *applied_mode != Some((settings.display_mode, settings.resolution)) {
+        *applied_mode = Some((settings.display_mode, settings.resolution));
+        window.mode = settings.display_mode.window_mode();
+        if settings.display_mode == DisplayMode::Windowed {
+            let (width, height) = settings.resolution;
+            window.resolution.set(width as f32, height as f32);
+        }
+    }
+    let present_mode = if settings.vsync {
+        PresentMode::AutoVsync
+    } else {
+        PresentMode::AutoNoVsync
+    };
+    if window.present_mode != present_mode {
+        window.present_mode = present_mode;
+    }
+    ui_scale.0 = settings.ui_scale;
+    egui_settings.scale_factor = settings.ui_scale;
+}
+
+fn save_settings(
+    settings: Res<Settings>,
+    mut egui_query: Query<&mut EguiContext, With<PrimaryWindow>>,
+    mut unsaved: Local<bool>,
+) {
+    if settings.is_changed() && !settings.is_added() {
+        *unsaved = true;
+    }
+    // Dragging a slider changes the settings every frame, so only write them once it is let go.
+    let dragging = egui_query
+        .get_single_mut()
+        .is_ok_and(|mut egui| egui.get_mut().dragged_id().is_some());
+    if !*unsaved || dragging {
+        return;
+    }
+    *unsaved = false;
+    if let Err(e) = settings.save() {
+        warn!("Failed to save settings: {e}");
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Settings {
+    fn path() -> Option<std::path::PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("shipping-friends").join("settings.ron"))
+    }
+
+    /// Read the settings file, falling back to the defaults if it is missing or unreadable.
+    pub fn load() -> Self {
+        let Some(contents) = Self::path().and_then(|path| std::fs::read_to_string(path).ok())
+        else {
+            return Self::default();
+        };
+        ron::from_str(&contents).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable settings file: {e}");
+            Self::default()
+        })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("no config directory")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
         }
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())?;
+        std::fs::write(path, contents).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(target_family = "wasm")]
+impl Settings {
+    pub fn load() -> Self {
+        Self::default()
+    }
+
+    fn save(&self) -> Result<(), String> {
+        Ok(())
     }
 }
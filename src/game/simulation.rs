@@ -0,0 +1,783 @@
+//! Headless turn resolution.
+//! Everything in here is plain data, so the rules can be run without a Bevy `World`.
+//! The ECS side builds a [`Board`] and lists of [`ShipState`]s and [`DockState`]s, hands
+//! them over along with each ship's [`TurnActions`], and copies the result back onto the
+//! entities.
+
+use crate::game::cargo::{CargoType, DockStock, Hold, TransferDirection};
+use crate::game::game_ui::{TurnAction, TurnActions};
+use crate::game::movement::Facing;
+use crate::game::spawn::level::LevelWalls;
+use bevy::reflect::Reflect;
+use bevy_ecs_ldtk::GridCoords;
+use std::str::FromStr;
+
+/// Number of steps in a single program.
+pub const PROGRAM_LENGTH: usize = 6;
+
+/// What happens when a programmed move is blocked by a wall or another ship.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect)]
+pub enum CollisionPolicy {
+    /// Stay put and carry on with the next action.
+    #[default]
+    SkipAction,
+    /// Stay put and wait out the rest of the program.
+    AbortProgram,
+    /// Get pushed back one tile, if there is room behind the ship.
+    BounceBack,
+    /// Stay put, lose a point of hull and carry on. A ship with no hull left is wrecked.
+    HullDamage,
+}
+
+impl FromStr for CollisionPolicy {
+    type Err = String;
+
+    /// Parse the identifiers used by the `CollisionPolicy` enum in LDtk.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "SkipAction" => Ok(Self::SkipAction),
+            "AbortProgram" => Ok(Self::AbortProgram),
+            "BounceBack" => Ok(Self::BounceBack),
+            "HullDamage" => Ok(Self::HullDamage),
+            _ => Err(format!("Unknown collision policy: {s}")),
+        }
+    }
+}
+
+/// The static parts of a level that the rules need to know about.
+#[derive(Clone, Copy, Debug)]
+pub struct Board<'a> {
+    pub walls: &'a LevelWalls,
+    pub collision_policy: CollisionPolicy,
+}
+
+impl<'a> Board<'a> {
+    pub fn new(walls: &'a LevelWalls, collision_policy: CollisionPolicy) -> Self {
+        Self {
+            walls,
+            collision_policy,
+        }
+    }
+
+    pub fn is_blocked(&self, coords: &GridCoords) -> bool {
+        self.walls.in_wall(coords)
+    }
+}
+
+/// A ship as seen by the simulation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ShipState {
+    pub facing: Facing,
+    pub coords: GridCoords,
+    pub hull: u32,
+    pub hold: Hold,
+}
+
+impl ShipState {
+    pub fn new(facing: Facing, coords: GridCoords, hull: u32, hold: Hold) -> Self {
+        Self {
+            facing,
+            coords,
+            hull,
+            hold,
+        }
+    }
+}
+
+/// A dock as seen by the simulation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DockState {
+    pub coords: GridCoords,
+    /// The direction the crane is facing.
+    pub facing: Facing,
+    pub stock: DockStock,
+}
+
+impl DockState {
+    pub fn new(coords: GridCoords, facing: Facing, stock: DockStock) -> Self {
+        Self {
+            coords,
+            facing,
+            stock,
+        }
+    }
+
+    /// The tile the crane faces, where a ship has to be to load or unload.
+    pub fn berth(&self) -> GridCoords {
+        let offset = self.facing.to_offset();
+        GridCoords::new(self.coords.x + offset.x, self.coords.y + offset.y)
+    }
+}
+
+/// Something that happened to a ship during a single step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SimEvent {
+    /// Index of the step within the program.
+    pub step: usize,
+    /// Index of the ship in the slice passed to the simulation.
+    pub ship: usize,
+    pub kind: SimEventKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimEventKind {
+    /// The ship had no action programmed for this step.
+    Waited,
+    Moved {
+        from: GridCoords,
+        to: GridCoords,
+    },
+    Rotated {
+        from: Facing,
+        to: Facing,
+    },
+    /// The ship tried to move into a wall (or off the map).
+    BlockedByWall {
+        at: GridCoords,
+        response: CollisionResponse,
+    },
+    /// The ship tried to move into a tile that another ship holds or was given priority for.
+    BlockedByShip {
+        at: GridCoords,
+        /// Index of the ship that got in the way.
+        other: usize,
+        response: CollisionResponse,
+    },
+    Loaded {
+        /// Index of the dock that was loaded from.
+        dock: usize,
+        cargo: CargoType,
+        amount: u32,
+    },
+    Unloaded {
+        /// Index of the dock that was unloaded to.
+        dock: usize,
+        cargo: CargoType,
+        amount: u32,
+    },
+    /// A load or unload that did nothing, either because no crane serves the ship's tile
+    /// or because there was nothing that could be moved.
+    CargoRefused {
+        dock: Option<usize>,
+    },
+    /// The ship ran out of hull and won't act again. Follows the collision that did it.
+    Wrecked,
+}
+
+impl SimEventKind {
+    /// The collision response, if this ship was blocked.
+    pub fn collision_response(&self) -> Option<CollisionResponse> {
+        match self {
+            Self::BlockedByWall { response, .. } | Self::BlockedByShip { response, .. } => {
+                Some(*response)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// How a blocked move was resolved, according to the level's [`CollisionPolicy`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollisionResponse {
+    Skipped,
+    /// The rest of this ship's program was dropped.
+    Aborted,
+    BouncedBack {
+        to: GridCoords,
+    },
+    Damaged {
+        hull: u32,
+    },
+}
+
+/// Step-by-step resolution of one program per ship.
+/// All ships execute step `n` of their program at the same time, before any ship executes
+/// step `n + 1`.
+///
+/// Ships never share a tile. Within a step, moves are settled like so:
+/// - Two ships trying to swap tiles head-on are both blocked.
+/// - A ship may move into a tile that another ship is leaving, as long as that ship
+///   actually gets to leave.
+/// - When several ships try to enter the same tile, the one with the lowest index wins
+///   and the rest are blocked.
+///
+/// Blocked ships are then dealt with by the level's [`CollisionPolicy`], again in index order.
+#[derive(Clone, Debug)]
+pub struct Simulation {
+    ships: Vec<ShipState>,
+    docks: Vec<DockState>,
+    programs: Vec<TurnActions>,
+    /// Ships whose program was cut short by [`CollisionPolicy::AbortProgram`],
+    /// or that were wrecked.
+    aborted: Vec<bool>,
+    step: usize,
+}
+
+impl Simulation {
+    /// `programs[i]` is the program for `ships[i]`.
+    pub fn new(ships: Vec<ShipState>, docks: Vec<DockState>, programs: Vec<TurnActions>) -> Self {
+        assert_eq!(
+            ships.len(),
+            programs.len(),
+            "every ship needs exactly one program"
+        );
+        Self {
+            aborted: vec![false; ships.len()],
+            ships,
+            docks,
+            programs,
+            step: 0,
+        }
+    }
+
+    pub fn ships(&self) -> &[ShipState] {
+        &self.ships
+    }
+
+    pub fn docks(&self) -> &[DockState] {
+        &self.docks
+    }
+
+    /// Index of the next step to be resolved.
+    pub fn current_step(&self) -> usize {
+        self.step
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.step >= PROGRAM_LENGTH
+    }
+
+    /// Resolve the next step for every ship, returning what happened.
+    /// Does nothing once the program is finished.
+    pub fn step(&mut self, board: &Board) -> Vec<SimEvent> {
+        let mut events = Vec::new();
+        if self.is_finished() {
+            return events;
+        }
+        let step = self.step;
+        let actions: Vec<Option<TurnAction>> = (0..self.ships.len())
+            .map(|ship_idx| match self.aborted[ship_idx] {
+                true => None,
+                false => self.programs[ship_idx].0[step],
+            })
+            .collect();
+
+        for (ship_idx, kind) in resolve_step(board, &mut self.ships, &mut self.docks, &actions)
+            .into_iter()
+            .enumerate()
+        {
+            let response = kind.collision_response();
+            if response == Some(CollisionResponse::Aborted) {
+                self.aborted[ship_idx] = true;
+            }
+            events.push(SimEvent {
+                step,
+                ship: ship_idx,
+                kind,
+            });
+            if response == Some(CollisionResponse::Damaged { hull: 0 }) {
+                self.aborted[ship_idx] = true;
+                events.push(SimEvent {
+                    step,
+                    ship: ship_idx,
+                    kind: SimEventKind::Wrecked,
+                });
+            }
+        }
+        self.step += 1;
+        events
+    }
+}
+
+/// What stopped a ship from moving.
+#[derive(Clone, Copy, Debug)]
+enum Blocker {
+    Wall,
+    Ship(usize),
+}
+
+/// Resolve a single step for every ship at once. `actions[i]` is what ship `i` does.
+fn resolve_step(
+    board: &Board,
+    ships: &mut [ShipState],
+    docks: &mut [DockState],
+    actions: &[Option<TurnAction>],
+) -> Vec<SimEventKind> {
+    let ship_count = ships.len();
+    let mut kinds: Vec<Option<SimEventKind>> = vec![None; ship_count];
+    // The tile each ship is trying to move into this step, if it is moving.
+    let mut targets: Vec<Option<GridCoords>> = vec![None; ship_count];
+    let mut blockers: Vec<Option<(GridCoords, Blocker)>> = vec![None; ship_count];
+    // Ships working a crane this step. They stay put, so they are dealt with after movement.
+    let mut transfers: Vec<(usize, TransferDirection)> = Vec::new();
+
+    for (ship_idx, ship) in ships.iter_mut().enumerate() {
+        let Some(action) = actions[ship_idx] else {
+            kinds[ship_idx] = Some(SimEventKind::Waited);
+            continue;
+        };
+        match action {
+            TurnAction::Load => {
+                transfers.push((ship_idx, TransferDirection::Load));
+                continue;
+            }
+            TurnAction::Unload => {
+                transfers.push((ship_idx, TransferDirection::Unload));
+                continue;
+            }
+            _ => {}
+        }
+        let (new_facing, new_coords) = action.apply(&ship.facing, &ship.coords);
+        if new_coords == ship.coords {
+            kinds[ship_idx] = Some(SimEventKind::Rotated {
+                from: ship.facing,
+                to: new_facing,
+            });
+            ship.facing = new_facing;
+        } else if board.is_blocked(&new_coords) {
+            blockers[ship_idx] = Some((new_coords, Blocker::Wall));
+        } else {
+            targets[ship_idx] = Some(new_coords);
+        }
+    }
+
+    // Head-on swaps.
+    for a in 0..ship_count {
+        for b in (a + 1)..ship_count {
+            if targets[a] == Some(ships[b].coords) && targets[b] == Some(ships[a].coords) {
+                blockers[a] = Some((ships[b].coords, Blocker::Ship(b)));
+                blockers[b] = Some((ships[a].coords, Blocker::Ship(a)));
+                targets[a] = None;
+                targets[b] = None;
+            }
+        }
+    }
+
+    // Keep blocking ships until every remaining move is into a free tile.
+    // Blocking a ship can only ever block more ships, so this always settles.
+    loop {
+        let mut changed = false;
+        for ship_idx in 0..ship_count {
+            let Some(target) = targets[ship_idx] else {
+                continue;
+            };
+            let staying_put = (0..ship_count).find(|&other| {
+                other != ship_idx && ships[other].coords == target && targets[other].is_none()
+            });
+            let contested = (0..ship_idx).find(|&other| targets[other] == Some(target));
+            if let Some(other) = staying_put.or(contested) {
+                blockers[ship_idx] = Some((target, Blocker::Ship(other)));
+                targets[ship_idx] = None;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    for (ship_idx, ship) in ships.iter_mut().enumerate() {
+        if let Some(to) = targets[ship_idx] {
+            kinds[ship_idx] = Some(SimEventKind::Moved {
+                from: ship.coords,
+                to,
+            });
+            ship.coords = to;
+        }
+    }
+
+    for (ship_idx, direction) in transfers {
+        kinds[ship_idx] = Some(transfer_cargo(&mut ships[ship_idx], docks, direction));
+    }
+
+    for ship_idx in 0..ship_count {
+        if let Some((at, blocker)) = blockers[ship_idx] {
+            let response = respond_to_collision(board, ships, ship_idx);
+            kinds[ship_idx] = Some(match blocker {
+                Blocker::Wall => SimEventKind::BlockedByWall { at, response },
+                Blocker::Ship(other) => SimEventKind::BlockedByShip {
+                    at,
+                    other,
+                    response,
+                },
+            });
+        }
+    }
+
+    kinds
+        .into_iter()
+        .map(|kind| kind.expect("every ship should have been resolved"))
+        .collect()
+}
+
+/// Load or unload at whichever crane serves the ship's tile.
+/// Loading takes as much as fits, unloading empties the hold.
+fn transfer_cargo(
+    ship: &mut ShipState,
+    docks: &mut [DockState],
+    direction: TransferDirection,
+) -> SimEventKind {
+    let Some(dock_idx) = docks.iter().position(|dock| dock.berth() == ship.coords) else {
+        return SimEventKind::CargoRefused { dock: None };
+    };
+    let refused = SimEventKind::CargoRefused {
+        dock: Some(dock_idx),
+    };
+    let dock = &mut docks[dock_idx];
+    match direction {
+        TransferDirection::Load => {
+            let Some(cargo) = dock.stock.cargo else {
+                return refused;
+            };
+            let amount = dock.stock.amount.min(ship.hold.space());
+            if amount == 0 || !ship.hold.can_take(cargo) {
+                return refused;
+            }
+            dock.stock.amount -= amount;
+            ship.hold.cargo = Some(cargo);
+            ship.hold.amount += amount;
+            SimEventKind::Loaded {
+                dock: dock_idx,
+                cargo,
+                amount,
+            }
+        }
+        TransferDirection::Unload => {
+            let Some(cargo) = ship.hold.cargo else {
+                return refused;
+            };
+            if ship.hold.amount == 0 || !dock.stock.accepts(cargo) {
+                return refused;
+            }
+            let amount = ship.hold.amount;
+            dock.stock.cargo = Some(cargo);
+            dock.stock.amount += amount;
+            ship.hold.cargo = None;
+            ship.hold.amount = 0;
+            SimEventKind::Unloaded {
+                dock: dock_idx,
+                cargo,
+                amount,
+            }
+        }
+    }
+}
+
+fn respond_to_collision(
+    board: &Board,
+    ships: &mut [ShipState],
+    ship_idx: usize,
+) -> CollisionResponse {
+    match board.collision_policy {
+        CollisionPolicy::SkipAction => CollisionResponse::Skipped,
+        CollisionPolicy::AbortProgram => CollisionResponse::Aborted,
+        CollisionPolicy::BounceBack => {
+            let ship = ships[ship_idx];
+            let offset = ship.facing.to_offset();
+            let behind = GridCoords::new(ship.coords.x - offset.x, ship.coords.y - offset.y);
+            let occupied = ships.iter().any(|other| other.coords == behind);
+            if board.is_blocked(&behind) || occupied {
+                CollisionResponse::Skipped
+            } else {
+                ships[ship_idx].coords = behind;
+                CollisionResponse::BouncedBack { to: behind }
+            }
+        }
+        CollisionPolicy::HullDamage => {
+            let ship = &mut ships[ship_idx];
+            ship.hull = ship.hull.saturating_sub(1);
+            CollisionResponse::Damaged { hull: ship.hull }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_water() -> LevelWalls {
+        LevelWalls::new(Default::default(), 8, 8)
+    }
+
+    fn ship(facing: Facing, x: i32, y: i32) -> ShipState {
+        ShipState::new(facing, GridCoords::new(x, y), 3, Hold::default())
+    }
+
+    fn program(actions: &[TurnAction]) -> TurnActions {
+        let mut program = [None; PROGRAM_LENGTH];
+        for (slot, action) in program.iter_mut().zip(actions) {
+            *slot = Some(*action);
+        }
+        TurnActions(program)
+    }
+
+    /// Run the first step of each program, returning what happened to each ship.
+    fn first_step(
+        policy: CollisionPolicy,
+        ships: Vec<ShipState>,
+        programs: Vec<TurnActions>,
+    ) -> (Vec<ShipState>, Vec<SimEventKind>) {
+        let walls = open_water();
+        let board = Board::new(&walls, policy);
+        let mut simulation = Simulation::new(ships, Vec::new(), programs);
+        let kinds = simulation
+            .step(&board)
+            .into_iter()
+            .map(|event| event.kind)
+            .collect();
+        (simulation.ships().to_vec(), kinds)
+    }
+
+    #[test]
+    fn head_on_swap_blocks_both_ships() {
+        let ships = vec![ship(Facing::East, 1, 1), ship(Facing::West, 2, 1)];
+        let (after, kinds) = first_step(
+            CollisionPolicy::SkipAction,
+            ships.clone(),
+            vec![
+                program(&[TurnAction::Forward]),
+                program(&[TurnAction::Forward]),
+            ],
+        );
+        assert_eq!(after, ships);
+        assert_eq!(
+            kinds,
+            vec![
+                SimEventKind::BlockedByShip {
+                    at: GridCoords::new(2, 1),
+                    other: 1,
+                    response: CollisionResponse::Skipped,
+                },
+                SimEventKind::BlockedByShip {
+                    at: GridCoords::new(1, 1),
+                    other: 0,
+                    response: CollisionResponse::Skipped,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn lowest_index_wins_a_contested_tile() {
+        let (after, kinds) = first_step(
+            CollisionPolicy::SkipAction,
+            vec![ship(Facing::East, 1, 1), ship(Facing::West, 3, 1)],
+            vec![
+                program(&[TurnAction::Forward]),
+                program(&[TurnAction::Forward]),
+            ],
+        );
+        assert_eq!(after[0].coords, GridCoords::new(2, 1));
+        assert_eq!(after[1].coords, GridCoords::new(3, 1));
+        assert_eq!(
+            kinds[1],
+            SimEventKind::BlockedByShip {
+                at: GridCoords::new(2, 1),
+                other: 0,
+                response: CollisionResponse::Skipped,
+            }
+        );
+    }
+
+    #[test]
+    fn ships_can_follow_each_other() {
+        let (after, _) = first_step(
+            CollisionPolicy::SkipAction,
+            vec![ship(Facing::East, 1, 1), ship(Facing::East, 2, 1)],
+            vec![
+                program(&[TurnAction::Forward]),
+                program(&[TurnAction::Forward]),
+            ],
+        );
+        assert_eq!(after[0].coords, GridCoords::new(2, 1));
+        assert_eq!(after[1].coords, GridCoords::new(3, 1));
+    }
+
+    #[test]
+    fn chain_is_blocked_by_a_stationary_ship() {
+        let ships = vec![
+            ship(Facing::East, 1, 1),
+            ship(Facing::East, 2, 1),
+            ship(Facing::East, 3, 1),
+        ];
+        let (after, kinds) = first_step(
+            CollisionPolicy::SkipAction,
+            ships.clone(),
+            vec![
+                program(&[TurnAction::Forward]),
+                program(&[TurnAction::Forward]),
+                program(&[]),
+            ],
+        );
+        assert_eq!(after, ships);
+        assert_eq!(
+            kinds,
+            vec![
+                SimEventKind::BlockedByShip {
+                    at: GridCoords::new(2, 1),
+                    other: 1,
+                    response: CollisionResponse::Skipped,
+                },
+                SimEventKind::BlockedByShip {
+                    at: GridCoords::new(3, 1),
+                    other: 2,
+                    response: CollisionResponse::Skipped,
+                },
+                SimEventKind::Waited,
+            ]
+        );
+    }
+
+    #[test]
+    fn wall_bump_under_each_policy() {
+        let cases = [
+            (
+                CollisionPolicy::SkipAction,
+                CollisionResponse::Skipped,
+                GridCoords::new(0, 1),
+                3,
+            ),
+            (
+                CollisionPolicy::AbortProgram,
+                CollisionResponse::Aborted,
+                GridCoords::new(0, 1),
+                3,
+            ),
+            (
+                CollisionPolicy::BounceBack,
+                CollisionResponse::BouncedBack {
+                    to: GridCoords::new(1, 1),
+                },
+                GridCoords::new(1, 1),
+                3,
+            ),
+            (
+                CollisionPolicy::HullDamage,
+                CollisionResponse::Damaged { hull: 2 },
+                GridCoords::new(0, 1),
+                2,
+            ),
+        ];
+        for (policy, response, coords, hull) in cases {
+            let walls = open_water();
+            let board = Board::new(&walls, policy);
+            let mut simulation = Simulation::new(
+                vec![ship(Facing::West, 0, 1)],
+                Vec::new(),
+                vec![program(&[TurnAction::Forward, TurnAction::RotateClockwise])],
+            );
+            let bump = simulation.step(&board);
+            assert_eq!(
+                bump[0].kind,
+                SimEventKind::BlockedByWall {
+                    at: GridCoords::new(-1, 1),
+                    response,
+                },
+                "{policy:?}"
+            );
+            assert_eq!(simulation.ships()[0].coords, coords, "{policy:?}");
+            assert_eq!(simulation.ships()[0].hull, hull, "{policy:?}");
+
+            // Only an aborted program stops the ship from carrying on.
+            let next = simulation.step(&board);
+            let waited = next[0].kind == SimEventKind::Waited;
+            assert_eq!(
+                waited,
+                policy == CollisionPolicy::AbortProgram,
+                "{policy:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn ship_is_wrecked_when_its_hull_runs_out() {
+        let walls = open_water();
+        let board = Board::new(&walls, CollisionPolicy::HullDamage);
+        let mut battered = ship(Facing::West, 0, 1);
+        battered.hull = 1;
+        let mut simulation = Simulation::new(
+            vec![battered],
+            Vec::new(),
+            vec![program(&[TurnAction::Forward, TurnAction::Forward])],
+        );
+        let kinds: Vec<_> = simulation
+            .step(&board)
+            .into_iter()
+            .map(|event| event.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                SimEventKind::BlockedByWall {
+                    at: GridCoords::new(-1, 1),
+                    response: CollisionResponse::Damaged { hull: 0 },
+                },
+                SimEventKind::Wrecked,
+            ]
+        );
+        // A wreck doesn't carry on with its program.
+        assert_eq!(simulation.step(&board)[0].kind, SimEventKind::Waited);
+    }
+
+    #[test]
+    fn cargo_moves_between_docks() {
+        let grain = DockStock {
+            cargo: Some(CargoType::Grain),
+            amount: 5,
+        };
+        let docks = vec![
+            // Serves (3, 2).
+            DockState::new(GridCoords::new(2, 2), Facing::East, grain),
+            // Serves (4, 2).
+            DockState::new(GridCoords::new(4, 1), Facing::North, DockStock::default()),
+        ];
+        let walls = open_water();
+        let board = Board::new(&walls, CollisionPolicy::SkipAction);
+        let mut simulation = Simulation::new(
+            vec![ship(Facing::East, 3, 2)],
+            docks,
+            vec![program(&[
+                TurnAction::Load,
+                TurnAction::Forward,
+                TurnAction::Unload,
+                TurnAction::Unload,
+            ])],
+        );
+        let mut kinds = Vec::new();
+        while !simulation.is_finished() {
+            kinds.extend(simulation.step(&board).into_iter().map(|event| event.kind));
+        }
+
+        assert_eq!(
+            kinds[0],
+            SimEventKind::Loaded {
+                dock: 0,
+                cargo: CargoType::Grain,
+                amount: 5,
+            }
+        );
+        assert_eq!(
+            kinds[2],
+            SimEventKind::Unloaded {
+                dock: 1,
+                cargo: CargoType::Grain,
+                amount: 5,
+            }
+        );
+        // The hold is empty by then.
+        assert_eq!(kinds[3], SimEventKind::CargoRefused { dock: Some(1) });
+        assert_eq!(simulation.docks()[0].stock.amount, 0);
+        assert_eq!(simulation.docks()[1].stock, grain);
+        assert_eq!(simulation.ships()[0].hold.amount, 0);
+    }
+
+    #[test]
+    fn loading_away_from_a_crane_is_refused() {
+        let (_, kinds) = first_step(
+            CollisionPolicy::SkipAction,
+            vec![ship(Facing::East, 1, 1)],
+            vec![program(&[TurnAction::Load])],
+        );
+        assert_eq!(kinds, vec![SimEventKind::CargoRefused { dock: None }]);
+    }
+}
@@ -0,0 +1,339 @@
+//! Shortest-plan search, used for in-game hints and for checking that levels can be solved.
+//! Like the [simulation](crate::game::simulation), this is plain data and runs without a Bevy `World`.
+
+use crate::game::game_ui::TurnAction;
+use crate::game::movement::Facing;
+use crate::game::simulation::DockState;
+use crate::game::spawn::level::LevelWalls;
+use bevy_ecs_ldtk::GridCoords;
+use std::collections::{HashMap, VecDeque};
+
+/// Somewhere a ship has to get to, and what it has to do once it's there.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stop {
+    pub coords: GridCoords,
+    /// `None` if getting there is enough.
+    pub action: Option<TurnAction>,
+}
+
+impl Stop {
+    /// End up on this tile, facing any way.
+    pub fn reach(coords: GridCoords) -> Self {
+        Self {
+            coords,
+            action: None,
+        }
+    }
+
+    /// Load at this dock's crane.
+    pub fn load_at(dock: &DockState) -> Self {
+        Self {
+            coords: dock.berth(),
+            action: Some(TurnAction::Load),
+        }
+    }
+
+    /// Unload at this dock's crane.
+    pub fn unload_at(dock: &DockState) -> Self {
+        Self {
+            coords: dock.berth(),
+            action: Some(TurnAction::Unload),
+        }
+    }
+}
+
+/// The programs a ship runs, one per cycle, to get something done.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Plan(pub Vec<Vec<TurnAction>>);
+
+impl Plan {
+    pub fn cycles(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn actions(&self) -> usize {
+        self.0.iter().map(Vec::len).sum()
+    }
+
+    /// The program for the first cycle, which is empty if there is nothing to do.
+    pub fn first_program(&self) -> &[TurnAction] {
+        self.0.first().map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Every action that can be programmed, in the order [`Node::used`] counts them.
+const ACTIONS: [TurnAction; 5] = [
+    TurnAction::Forward,
+    TurnAction::RotateClockwise,
+    TurnAction::RotateAntiClockwise,
+    TurnAction::Load,
+    TurnAction::Unload,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Node {
+    facing: Facing,
+    coords: GridCoords,
+    /// How many of each of [`ACTIONS`] have been spent so far.
+    used: [usize; ACTIONS.len()],
+    /// Index of the next stop to make.
+    stop: usize,
+}
+
+impl Node {
+    /// Pass through any stops that only need the ship to be here.
+    fn arrive(mut self, stops: &[Stop]) -> Self {
+        while stops
+            .get(self.stop)
+            .is_some_and(|stop| stop.action.is_none() && stop.coords == self.coords)
+        {
+            self.stop += 1;
+        }
+        self
+    }
+
+    /// Where the ship is and how far along the stops it is, whatever it spent to get there.
+    fn place(&self) -> (Facing, GridCoords, usize) {
+        (self.facing, self.coords, self.stop)
+    }
+}
+
+/// A node found by the search, and how it was reached.
+struct Visit {
+    node: Node,
+    parent: Option<usize>,
+    /// The action programmed to get here, or `None` for the start.
+    action: Option<TurnAction>,
+}
+
+/// Find the plan that makes each of `stops` in turn with the fewest actions, without running
+/// into a wall. Each action in `store` can only be spent once over the whole plan.
+/// The actions are split into programs of up to `turns_per_cycle`, one per cycle, so the plan
+/// also takes the fewest cycles, and it can take at most `max_cycles` of them.
+/// Other ships, and whether docks have the cargo, are not taken into account.
+///
+/// Returns `None` if there is no such plan, and an empty plan if there is nothing to do.
+pub fn plan(
+    walls: &LevelWalls,
+    facing: Facing,
+    coords: GridCoords,
+    stops: &[Stop],
+    store: &[TurnAction],
+    turns_per_cycle: usize,
+    max_cycles: Option<usize>,
+) -> Option<Plan> {
+    if stops.iter().any(|stop| walls.in_wall(&stop.coords)) {
+        return None;
+    }
+    let available = ACTIONS.map(|action| store.iter().filter(|stored| **stored == action).count());
+    let max_actions = max_cycles.map(|max| max * turns_per_cycle);
+
+    let start = Node {
+        facing,
+        coords,
+        used: [0; ACTIONS.len()],
+        stop: 0,
+    }
+    .arrive(stops);
+    let mut visits = vec![Visit {
+        node: start,
+        parent: None,
+        action: None,
+    }];
+    // What was spent on each way of reaching a place, leaving out any way that spent at least
+    // as many of every action as another one did.
+    let mut reached = HashMap::from([(start.place(), vec![start.used])]);
+    // Every action costs the same, so searching breadth first finds the fewest actions.
+    let mut queue = VecDeque::from([0]);
+    while let Some(idx) = queue.pop_front() {
+        let node = visits[idx].node;
+        if node.stop == stops.len() {
+            return Some(build_plan(&visits, idx, turns_per_cycle));
+        }
+        let spent: usize = node.used.iter().sum();
+        if max_actions.is_some_and(|max| spent >= max) {
+            continue;
+        }
+
+        for (action_idx, action) in ACTIONS.iter().enumerate() {
+            if node.used[action_idx] >= available[action_idx] {
+                continue;
+            }
+            let mut used = node.used;
+            used[action_idx] += 1;
+            let next = match action {
+                TurnAction::Load | TurnAction::Unload => {
+                    // Only worth doing at the stop that asks for it.
+                    let stop = stops[node.stop];
+                    if stop.action != Some(*action) || stop.coords != node.coords {
+                        continue;
+                    }
+                    Node {
+                        used,
+                        stop: node.stop + 1,
+                        ..node
+                    }
+                }
+                _ => {
+                    let (facing, coords) = action.apply(&node.facing, &node.coords);
+                    if walls.in_wall(&coords) {
+                        continue;
+                    }
+                    Node {
+                        facing,
+                        coords,
+                        used,
+                        ..node
+                    }
+                }
+            }
+            .arrive(stops);
+
+            let ways = reached.entry(next.place()).or_default();
+            if ways
+                .iter()
+                .any(|way| way.iter().zip(&next.used).all(|(way, next)| way <= next))
+            {
+                continue;
+            }
+            ways.push(next.used);
+            visits.push(Visit {
+                node: next,
+                parent: Some(idx),
+                action: Some(*action),
+            });
+            queue.push_back(visits.len() - 1);
+        }
+    }
+    None
+}
+
+/// Follow the visits back from `idx` to the start, splitting the actions into cycles.
+fn build_plan(visits: &[Visit], mut idx: usize, turns_per_cycle: usize) -> Plan {
+    let mut actions = Vec::new();
+    while let Some(parent) = visits[idx].parent {
+        actions.extend(visits[idx].action);
+        idx = parent;
+    }
+    actions.reverse();
+    Plan(
+        actions
+            .chunks(turns_per_cycle)
+            .map(<[TurnAction]>::to_vec)
+            .collect(),
+    )
+}
+
+/// Find the quickest plan to get a ship from `facing` and `coords` to `target`.
+/// See [`plan`].
+pub fn solve(
+    walls: &LevelWalls,
+    facing: Facing,
+    coords: GridCoords,
+    target: GridCoords,
+    store: &[TurnAction],
+    turns_per_cycle: usize,
+    max_cycles: Option<usize>,
+) -> Option<Plan> {
+    plan(
+        walls,
+        facing,
+        coords,
+        &[Stop::reach(target)],
+        store,
+        turns_per_cycle,
+        max_cycles,
+    )
+}
+
+/// Find the quickest plan that loads at `from` and then unloads at `to`.
+/// See [`plan`].
+pub fn solve_delivery(
+    walls: &LevelWalls,
+    facing: Facing,
+    coords: GridCoords,
+    from: &DockState,
+    to: &DockState,
+    store: &[TurnAction],
+    turns_per_cycle: usize,
+    max_cycles: Option<usize>,
+) -> Option<Plan> {
+    plan(
+        walls,
+        facing,
+        coords,
+        &[Stop::load_at(from), Stop::unload_at(to)],
+        store,
+        turns_per_cycle,
+        max_cycles,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::game_ui::CycleStore;
+    use crate::game::simulation::PROGRAM_LENGTH;
+
+    fn open_water() -> LevelWalls {
+        LevelWalls::new(Default::default(), 8, 8)
+    }
+
+    fn forwards(count: usize) -> Vec<TurnAction> {
+        vec![TurnAction::Forward; count]
+    }
+
+    #[test]
+    fn long_routes_take_several_cycles() {
+        let plan = solve(
+            &open_water(),
+            Facing::East,
+            GridCoords::new(0, 0),
+            GridCoords::new(7, 0),
+            &forwards(7),
+            PROGRAM_LENGTH,
+            None,
+        )
+        .unwrap();
+        assert_eq!(plan.cycles(), 2);
+        assert_eq!(plan.actions(), 7);
+        assert_eq!(plan.first_program(), &[TurnAction::Forward; PROGRAM_LENGTH]);
+    }
+
+    #[test]
+    fn max_cycles_limits_the_plan() {
+        let store = forwards(7);
+        let walls = open_water();
+        let plan = |max_cycles| {
+            solve(
+                &walls,
+                Facing::East,
+                GridCoords::new(0, 0),
+                GridCoords::new(7, 0),
+                &store,
+                PROGRAM_LENGTH,
+                max_cycles,
+            )
+        };
+        assert!(plan(Some(1)).is_none());
+        assert!(plan(Some(2)).is_some());
+    }
+
+    #[test]
+    fn actions_are_only_spent_once() {
+        let store = CycleStore::new().available();
+        let plan = |target| {
+            solve(
+                &open_water(),
+                Facing::East,
+                GridCoords::new(0, 0),
+                target,
+                &store,
+                PROGRAM_LENGTH,
+                None,
+            )
+        };
+        assert!(plan(GridCoords::new(3, 0)).is_some());
+        assert!(plan(GridCoords::new(4, 0)).is_none());
+    }
+}
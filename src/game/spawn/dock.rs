@@ -1,7 +1,8 @@
+use crate::game::cargo::DockStock;
 use crate::game::movement::Facing;
 use crate::game::spawn::level::GRID_SIZE_V;
-use crate::game::spawn::player::Player;
 use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::ldtk_fields::LdtkFields;
 use bevy_ecs_ldtk::prelude::*;
 
 pub(super) fn plugin(app: &mut App) {
@@ -9,6 +10,7 @@ pub(super) fn plugin(app: &mut App) {
     app.register_ldtk_entity::<DockBundle>("Crane_E");
     app.register_ldtk_entity::<DockBundle>("Crane_S");
     app.register_ldtk_entity::<DockBundle>("Crane_W");
+    app.register_type::<(Dock, DockName)>();
     app.add_systems(Update, fix_dock_grid_coord_positions);
 }
 #[derive(Default, Bundle, LdtkEntity)]
@@ -20,11 +22,36 @@ struct DockBundle {
     grid_coords: GridCoords,
     #[from_entity_instance]
     facing: Facing,
+    #[from_entity_instance]
+    stock: DockStock,
+    #[from_entity_instance]
+    name: DockName,
 }
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
 #[reflect(Component)]
 pub struct Dock;
+
+/// What the player calls a dock, and how contracts refer to it.
+#[derive(Component, Debug, Clone, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct DockName(pub String);
+
+impl Default for DockName {
+    fn default() -> Self {
+        Self("Dock".to_string())
+    }
+}
+
+impl From<&EntityInstance> for DockName {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        entity_instance
+            .get_string_field("Name")
+            .map(|name| Self(name.clone()))
+            .unwrap_or_default()
+    }
+}
+
 fn fix_dock_grid_coord_positions(
     mut docks: Query<(&mut Transform, &Facing, &GridCoords), With<Dock>>,
     mut level_events: EventReader<LevelEvent>,
@@ -32,28 +59,16 @@ fn fix_dock_grid_coord_positions(
     for level_event in level_events.read() {
         if let LevelEvent::Spawned(_) = level_event {
             for (mut transform, facing, grid_coords) in &mut docks {
-                // TODO: Offset by 10.0 away form facing
-                let offset = facing.to_offset().as_vec2() * -10.0;
-                transform.translation =
-                    (bevy_ecs_ldtk::utils::grid_coords_to_translation(*grid_coords, GRID_SIZE_V)
-                        + offset)
-                        // Z should be above player boat
-                        .extend(3.0);
+                transform.translation = dock_translation(*facing, *grid_coords);
             }
         }
     }
 }
 
-impl From<&EntityInstance> for Facing {
-    fn from(entity_instance: &EntityInstance) -> Self {
-        // I *should* be using the enum in EntityInstance.field_instances to
-        // determine the facing, but using the name is just as good
-        match entity_instance.identifier.as_str() {
-            "Crane_N" => Self::North,
-            "Crane_E" => Self::East,
-            "Crane_S" => Self::South,
-            "Crane_W" => Self::West,
-            _ => panic!("Unexpected entity instance: {:?}", entity_instance),
-        }
-    }
+/// Where a dock's sprite goes: set back a little from the water it faces,
+/// and above the ships so the crane hangs over them.
+pub fn dock_translation(facing: Facing, grid_coords: GridCoords) -> Vec3 {
+    let offset = facing.to_offset().as_vec2() * -10.0;
+    (bevy_ecs_ldtk::utils::grid_coords_to_translation(grid_coords, GRID_SIZE_V) + offset)
+        .extend(3.0)
 }
@@ -0,0 +1,32 @@
+//! Spawn the goal tiles that ships have to reach.
+
+use crate::game::spawn::level::GRID_SIZE;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_ldtk_entity::<GoalBundle>("Goal");
+    app.register_type::<Goal>();
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+#[reflect(Component)]
+pub struct Goal;
+
+#[derive(Default, Bundle, LdtkEntity)]
+struct GoalBundle {
+    goal: Goal,
+    #[grid_coords]
+    grid_coords: GridCoords,
+    #[with(goal_sprite)]
+    sprite: Sprite,
+    texture: Handle<Image>,
+}
+
+fn goal_sprite(_: &EntityInstance) -> Sprite {
+    Sprite {
+        color: Color::srgba(0.91, 0.77, 0.28, 0.5),
+        custom_size: Some(Vec2::splat(GRID_SIZE as f32)),
+        ..default()
+    }
+}
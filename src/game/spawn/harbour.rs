@@ -0,0 +1,487 @@
+//! Generated practice harbours, as an endless alternative to the campaign levels.
+//!
+//! A layout is only accepted once the [solver](crate::game::solver) has found a way to make
+//! its delivery, and a fixed layout is used if no seeded one turns up in a few attempts.
+//! It is spawned with the same components LDtk gives its levels,
+//! and fills in [`LevelWalls`], [`LevelRules`] and [`Contracts`] itself.
+
+use crate::game::assets::{AtlasLayoutKey, HandleMap, ImageKey};
+use crate::game::cargo::{CargoType, DockStock, Hold};
+use crate::game::contracts::{Contract, ContractStatus, Contracts};
+use crate::game::game_ui::{CycleNum, CycleStore, TurnAction};
+use crate::game::level_check::LevelLayout;
+use crate::game::movement::Facing;
+use crate::game::rng::GameRng;
+use crate::game::simulation::DockState;
+use crate::game::solver::{solve_delivery, Plan};
+use crate::game::spawn::dock::{dock_translation, Dock, DockName};
+use crate::game::spawn::level::{LevelRules, LevelWalls, Wall, GRID_SIZE, GRID_SIZE_V};
+use crate::game::spawn::player::ShipBundle;
+use crate::screen::Screen;
+use bevy::prelude::*;
+use bevy::utils::hashbrown::HashSet;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<PracticeHarbour>();
+    app.observe(spawn_harbour);
+    app.add_systems(OnEnter(Screen::Title), end_practice);
+}
+
+/// Play a generated harbour instead of a campaign level.
+/// Levels are spawned from this seed for as long as it is present.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct PracticeHarbour {
+    pub seed: u64,
+}
+
+impl PracticeHarbour {
+    pub fn random(game_rng: &mut GameRng) -> Self {
+        Self {
+            seed: game_rng.next_seed(),
+        }
+    }
+}
+
+/// Triggered to spawn the harbour generated from a seed.
+#[derive(Event, Debug)]
+pub struct SpawnHarbour(pub u64);
+
+/// The same size as the campaign levels, in tiles.
+pub const HARBOUR_WIDTH: i32 = 14;
+pub const HARBOUR_HEIGHT: i32 = 12;
+
+/// How far apart, in tiles, the berths of the two docks can be.
+const MAX_BERTH_DISTANCE: i32 = 4;
+
+/// How many random layouts to try before falling back to [`fallback_harbour`].
+const MAX_ATTEMPTS: usize = 32;
+
+/// How many places the solver tries starting the ship from in each layout.
+const START_SAMPLES: usize = 16;
+
+/// The actions a harbour's ship has to spend, enough for the delivery and a little more.
+const HARBOUR_ACTIONS: [(TurnAction, usize); 5] = [
+    (TurnAction::Forward, 12),
+    (TurnAction::RotateClockwise, 2),
+    (TurnAction::RotateAntiClockwise, 2),
+    (TurnAction::Load, 1),
+    (TurnAction::Unload, 1),
+];
+
+fn harbour_inventory() -> Vec<TurnAction> {
+    HARBOUR_ACTIONS
+        .iter()
+        .flat_map(|&(action, count)| std::iter::repeat(action).take(count))
+        .collect()
+}
+
+const DOCK_NAMES: [&str; 6] = [
+    "Granary",
+    "Warehouse",
+    "Coal Yard",
+    "Container Terminal",
+    "Timber Wharf",
+    "Fish Market",
+];
+
+/// Indices into the [`ImageKey::BasicTileSet`] tiles.
+const LAND_TILE: u32 = 0;
+const WATER_TILE: u32 = 1;
+
+const SHIP_IID: &str = "harbour-ship-0";
+
+fn dock_iid(idx: usize) -> String {
+    format!("harbour-dock-{idx}")
+}
+
+/// A generated harbour with a single delivery to make.
+#[derive(Clone, Debug)]
+pub struct HarbourLayout {
+    pub land: HashSet<GridCoords>,
+    /// The dock to load at, then the dock to unload at.
+    pub docks: [(DockName, DockState); 2],
+    pub ship: (Facing, GridCoords),
+    pub cargo: CargoType,
+    pub amount: u32,
+    /// The plan the solver found for the delivery.
+    pub solution: Plan,
+}
+
+impl HarbourLayout {
+    pub fn walls(&self) -> LevelWalls {
+        LevelWalls::new(self.land.clone(), HARBOUR_WIDTH, HARBOUR_HEIGHT)
+    }
+
+    /// The number of cycles the solution takes.
+    pub fn cycles(&self) -> usize {
+        self.solution.cycles()
+    }
+
+    pub fn rules(&self) -> LevelRules {
+        LevelRules {
+            complete_contracts: true,
+            // A spare cycle to recover from a mistake.
+            cycle_budget: Some(self.cycles() + 1),
+            par_cycles: Some(self.cycles()),
+            par_actions: Some(self.solution.actions()),
+            actions: Some(harbour_inventory()),
+            ..default()
+        }
+    }
+
+    /// The harbour as it will be spawned, with the same iids.
+    pub fn level_layout(&self) -> LevelLayout {
+        let (facing, coords) = self.ship;
+        LevelLayout {
+            rules: self.rules(),
+            walls: self.walls(),
+            ships: vec![(SHIP_IID.to_string(), facing, coords)],
+            docks: self
+                .docks
+                .iter()
+                .enumerate()
+                .map(|(idx, (name, dock))| (dock_iid(idx), name.clone(), *dock))
+                .collect(),
+            goals: Vec::new(),
+        }
+    }
+}
+
+/// Generate a harbour that can be solved with the actions its ship is given.
+/// The same seed always gives the same harbour, even across versions of `rand`.
+pub fn generate_harbour(seed: u64) -> HarbourLayout {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let inventory = harbour_inventory();
+    let turns_per_cycle = CycleNum::default().turns_per_cycle();
+    (0..MAX_ATTEMPTS)
+        .find_map(|_| try_generate_harbour(&mut rng, &inventory, turns_per_cycle))
+        .unwrap_or_else(|| {
+            warn!("No harbour found for seed {seed}, using the fallback layout");
+            fallback_harbour(&inventory, turns_per_cycle)
+        })
+}
+
+/// A plain channel between two shores, with the docks on opposite sides.
+fn fallback_harbour(inventory: &[TurnAction], turns_per_cycle: usize) -> HarbourLayout {
+    let land: HashSet<GridCoords> = grid_coords()
+        .filter(|coords| coords.y < 2 || coords.y >= HARBOUR_HEIGHT - 2)
+        .collect();
+    let walls = LevelWalls::new(land.clone(), HARBOUR_WIDTH, HARBOUR_HEIGHT);
+    let cargo = CargoType::ALL[0];
+    let amount = 4;
+    let from = DockState::new(
+        GridCoords::new(3, 1),
+        Facing::North,
+        DockStock {
+            cargo: Some(cargo),
+            amount,
+        },
+    );
+    let to = DockState::new(
+        GridCoords::new(6, HARBOUR_HEIGHT - 2),
+        Facing::South,
+        DockStock::default(),
+    );
+    let ship = (Facing::East, GridCoords::new(1, 2));
+    let solution = solve_delivery(
+        &walls,
+        ship.0,
+        ship.1,
+        &from,
+        &to,
+        inventory,
+        turns_per_cycle,
+        None,
+    )
+    .expect("the fallback harbour can always be solved");
+    HarbourLayout {
+        land,
+        docks: [
+            (DockName(DOCK_NAMES[0].to_string()), from),
+            (DockName(DOCK_NAMES[1].to_string()), to),
+        ],
+        ship,
+        cargo,
+        amount,
+        solution,
+    }
+}
+
+fn try_generate_harbour(
+    rng: &mut ChaCha8Rng,
+    inventory: &[TurnAction],
+    turns_per_cycle: usize,
+) -> Option<HarbourLayout> {
+    let mut land = HashSet::new();
+    // Shoreline along the top and bottom, with a few islands out in the water.
+    for x in 0..HARBOUR_WIDTH {
+        for y in 0..rng.gen_range(1..=3) {
+            land.insert(GridCoords::new(x, y));
+        }
+        for y in HARBOUR_HEIGHT - rng.gen_range(1..=3)..HARBOUR_HEIGHT {
+            land.insert(GridCoords::new(x, y));
+        }
+    }
+    for _ in 0..rng.gen_range(0..=3) {
+        let x = rng.gen_range(1..HARBOUR_WIDTH - 2);
+        let y = rng.gen_range(4..HARBOUR_HEIGHT - 5);
+        for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            if rng.gen_bool(0.75) {
+                land.insert(GridCoords::new(x + dx, y + dy));
+            }
+        }
+    }
+    let walls = LevelWalls::new(land.clone(), HARBOUR_WIDTH, HARBOUR_HEIGHT);
+
+    // Cranes stand on land, facing the water they load from.
+    // Sites are listed in grid order so that the seed alone decides the choice.
+    let sites: Vec<DockState> = grid_coords()
+        .filter(|coords| land.contains(coords))
+        .flat_map(|coords| {
+            Facing::ALL.map(|facing| DockState::new(coords, facing, DockStock::default()))
+        })
+        .filter(|dock| !walls.in_wall(&dock.berth()))
+        .collect();
+    let mut from = *sites.choose(rng)?;
+    let nearby: Vec<&DockState> = sites
+        .iter()
+        .filter(|dock| {
+            let (berth, from_berth) = (dock.berth(), from.berth());
+            dock.coords != from.coords
+                && berth != from_berth
+                && (berth.x - from_berth.x).abs() + (berth.y - from_berth.y).abs()
+                    <= MAX_BERTH_DISTANCE
+        })
+        .collect();
+    let to = **nearby.choose(rng)?;
+
+    let cargo = *CargoType::ALL.choose(rng)?;
+    let amount = rng.gen_range(2..=Hold::default().capacity.min(6));
+    from.stock = DockStock {
+        cargo: Some(cargo),
+        amount,
+    };
+
+    // Start the ship as far from making the delivery as it can be while still managing it,
+    // out of a sample of the places it could start from.
+    let berths = [from.berth(), to.berth()];
+    let candidates: Vec<(Facing, GridCoords)> = grid_coords()
+        .filter(|coords| !walls.in_wall(coords) && !berths.contains(coords))
+        .flat_map(|coords| Facing::ALL.map(|facing| (facing, coords)))
+        .collect();
+    let starts: Vec<(Facing, GridCoords, Plan)> = candidates
+        .choose_multiple(rng, START_SAMPLES)
+        .filter_map(|&(facing, coords)| {
+            let solution = solve_delivery(
+                &walls,
+                facing,
+                coords,
+                &from,
+                &to,
+                inventory,
+                turns_per_cycle,
+                None,
+            )?;
+            Some((facing, coords, solution))
+        })
+        .collect();
+    let length = |solution: &Plan| (solution.cycles(), solution.actions());
+    let longest = starts.iter().map(|(.., solution)| length(solution)).max()?;
+    let longest_starts: Vec<_> = starts
+        .into_iter()
+        .filter(|(.., solution)| length(solution) == longest)
+        .collect();
+    let (facing, coords, solution) = longest_starts.choose(rng)?.clone();
+
+    let mut names = DOCK_NAMES.choose_multiple(rng, 2);
+    let mut next_name = || DockName(names.next().copied().unwrap_or_default().to_string());
+    Some(HarbourLayout {
+        land,
+        docks: [(next_name(), from), (next_name(), to)],
+        ship: (facing, coords),
+        cargo,
+        amount,
+        solution,
+    })
+}
+
+fn grid_coords() -> impl Iterator<Item = GridCoords> {
+    (0..HARBOUR_HEIGHT).flat_map(|y| (0..HARBOUR_WIDTH).map(move |x| GridCoords::new(x, y)))
+}
+
+fn end_practice(mut commands: Commands) {
+    commands.remove_resource::<PracticeHarbour>();
+}
+
+fn spawn_harbour(
+    trigger: Trigger<SpawnHarbour>,
+    mut commands: Commands,
+    image_handles: Res<HandleMap<ImageKey>>,
+    atlas_layout_handles: Res<HandleMap<AtlasLayoutKey>>,
+    mut level_walls: ResMut<LevelWalls>,
+    mut level_rules: ResMut<LevelRules>,
+    mut contracts: ResMut<Contracts>,
+) {
+    let layout = generate_harbour(trigger.event().0);
+    let cycles = layout.cycles();
+
+    *level_walls = layout.walls();
+    *level_rules = layout.rules();
+
+    let harbour = commands
+        .spawn((
+            Name::new("Harbour"),
+            SpatialBundle::default(),
+            StateScoped(Screen::Playing),
+        ))
+        .id();
+
+    // Land tiles are `Wall`s, just like the LDtk IntGrid cells.
+    let map_size = TilemapSize {
+        x: HARBOUR_WIDTH as u32,
+        y: HARBOUR_HEIGHT as u32,
+    };
+    let tilemap = commands.spawn(Name::new("Tiles")).set_parent(harbour).id();
+    let mut tile_storage = TileStorage::empty(map_size);
+    commands.entity(tilemap).with_children(|children| {
+        for coords in grid_coords() {
+            let position = TilePos {
+                x: coords.x as u32,
+                y: coords.y as u32,
+            };
+            let is_land = layout.land.contains(&coords);
+            let mut tile = children.spawn((
+                TileBundle {
+                    position,
+                    tilemap_id: TilemapId(tilemap),
+                    texture_index: TileTextureIndex(if is_land { LAND_TILE } else { WATER_TILE }),
+                    ..default()
+                },
+                coords,
+            ));
+            if is_land {
+                tile.insert(Wall);
+            }
+            tile_storage.set(&position, tile.id());
+        }
+    });
+    // The tileset's tiles are twice the size of a grid cell.
+    let tile_size = TilemapTileSize { x: 100.0, y: 100.0 };
+    commands.entity(tilemap).insert(TilemapBundle {
+        grid_size: tile_size.into(),
+        map_type: TilemapType::default(),
+        size: map_size,
+        storage: tile_storage,
+        texture: TilemapTexture::Single(image_handles[&ImageKey::BasicTileSet].clone()),
+        tile_size,
+        transform: Transform::from_translation(Vec3::new(
+            GRID_SIZE as f32 / 2.0,
+            GRID_SIZE as f32 / 2.0,
+            0.0,
+        ))
+        .with_scale(Vec3::splat(0.5)),
+        ..default()
+    });
+
+    let docks: Vec<Entity> = layout
+        .docks
+        .iter()
+        .enumerate()
+        .map(|(idx, (name, dock))| {
+            commands
+                .spawn((
+                    Name::new(name.0.clone()),
+                    Dock,
+                    name.clone(),
+                    dock.facing,
+                    dock.stock,
+                    dock.coords,
+                    EntityIid::new(dock_iid(idx)),
+                    SpriteBundle {
+                        texture: image_handles[&ImageKey::LoadingCrane].clone(),
+                        // The crane is drawn reaching down, rather than to the east.
+                        transform: Transform::from_translation(dock_translation(
+                            dock.facing,
+                            dock.coords,
+                        ))
+                        .with_rotation(
+                            dock.facing.to_rotation()
+                                * Quat::from_rotation_z(std::f32::consts::PI / 2.0),
+                        )
+                        .with_scale(Vec3::splat(0.5)),
+                        ..default()
+                    },
+                    TextureAtlas {
+                        layout: atlas_layout_handles[&AtlasLayoutKey::LoadingCrane].clone(),
+                        index: 0,
+                    },
+                ))
+                .set_parent(harbour)
+                .id()
+        })
+        .collect();
+
+    let (facing, coords) = layout.ship;
+    commands
+        .spawn((
+            ShipBundle::new(coords, facing),
+            EntityIid::new(SHIP_IID),
+            SpatialBundle::from_transform(
+                Transform::from_translation(
+                    bevy_ecs_ldtk::utils::grid_coords_to_translation(coords, GRID_SIZE_V)
+                        .extend(0.0),
+                )
+                .with_rotation(facing.to_rotation()),
+            ),
+        ))
+        // Harbours aren't LDtk levels, so the ship doesn't get its actions from `read_level_rules`.
+        .insert(CycleStore::with_actions(level_rules.inventory()))
+        .set_parent(harbour);
+
+    contracts.0 = vec![Contract {
+        cargo: layout.cargo,
+        amount: layout.amount,
+        delivered: 0,
+        from: docks[0],
+        to: docks[1],
+        deadline: cycles + 1,
+        status: ContractStatus::Active,
+    }];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fallback_harbour_can_be_solved() {
+        let layout = fallback_harbour(&harbour_inventory(), CycleNum::default().turns_per_cycle());
+        assert!(layout.cycles() > 1);
+    }
+
+    #[test]
+    fn generated_harbours_can_be_solved() {
+        for seed in 0..20 {
+            let layout = generate_harbour(seed);
+            let [(_, from), (_, to)] = &layout.docks;
+            let (facing, coords) = layout.ship;
+            let solution = solve_delivery(
+                &layout.walls(),
+                facing,
+                coords,
+                from,
+                to,
+                &harbour_inventory(),
+                CycleNum::default().turns_per_cycle(),
+                None,
+            );
+            assert_eq!(solution.as_ref(), Some(&layout.solution), "seed {seed}");
+        }
+    }
+}
@@ -1,24 +1,36 @@
-//! Spawn the main level by triggering other observers.
-
-use super::player::SpawnPlayer;
-use crate::game::assets::{HandleMap, ImageKey, LdtkKey};
+//! Spawn the main level. Ships and docks are spawned by LDtk from the level's entities,
+//! unless a [generated harbour](super::harbour) is being played instead.
+
+use crate::game::assets::{HandleMap, LdtkKey};
+use crate::game::campaign::{campaign_levels, current_level_index};
+use crate::game::game_ui::{CycleStore, TurnAction};
+use crate::game::rng::{level_seed, GameRng};
+use crate::game::save::PendingSnapshot;
+use crate::game::simulation::CollisionPolicy;
+use crate::game::spawn::harbour::{PracticeHarbour, SpawnHarbour};
+use crate::game::spawn::player::Player;
 use crate::screen::Screen;
 use bevy::prelude::*;
 use bevy::utils::hashbrown::HashSet;
+use bevy_ecs_ldtk::ldtk::ldtk_fields::LdtkFields;
+use bevy_ecs_ldtk::ldtk::Level;
 use bevy_ecs_ldtk::prelude::*;
-use bevy_ecs_tilemap::prelude::*;
 
 pub(super) fn plugin(app: &mut App) {
     app.observe(spawn_level);
     app.init_resource::<LevelWalls>();
     app.register_type::<LevelWalls>();
+    app.init_resource::<LevelRules>();
+    app.register_type::<LevelRules>();
 
-    app.insert_resource(LevelSelection::index(0))
-        .register_ldtk_int_cell::<WallBundle>(1);
+    app.register_ldtk_int_cell::<WallBundle>(WALL_INT_CELL);
 
-    app.add_systems(Update, cache_wall_locations);
+    app.add_systems(Update, (cache_wall_locations, read_level_rules));
 }
 
+/// The value of wall cells in the LDtk IntGrid layer.
+pub const WALL_INT_CELL: i32 = 1;
+
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
 #[reflect(Component)]
 pub struct Wall;
@@ -31,84 +43,53 @@ pub struct WallBundle {
 pub const GRID_SIZE: i32 = 50;
 pub const GRID_SIZE_V: IVec2 = IVec2::new(GRID_SIZE, GRID_SIZE);
 
-#[derive(Resource, Debug, Clone, Default, Reflect)]
-#[reflect(Resource)]
-pub struct TilemapOffset(pub Transform);
-
 #[derive(Event, Debug)]
 pub struct SpawnLevel;
 
 fn spawn_level(
     _trigger: Trigger<SpawnLevel>,
     mut commands: Commands,
-    mut ldtk_handles: Res<HandleMap<LdtkKey>>,
+    ldtk_handles: Res<HandleMap<LdtkKey>>,
+    ldtk_projects: Res<Assets<LdtkProject>>,
+    level_selection: Res<LevelSelection>,
+    practice_harbour: Option<Res<PracticeHarbour>>,
+    pending_snapshot: Option<Res<PendingSnapshot>>,
+    mut game_rng: ResMut<GameRng>,
+    mut next_screen: ResMut<NextState<Screen>>,
 ) {
-    commands.trigger(SpawnPlayer);
-
+    // Generated harbours are seeded with the seed they were generated from,
+    // so that everyone playing one gets the same game.
+    let seed = if let Some(practice_harbour) = &practice_harbour {
+        practice_harbour.seed
+    } else if let Some(pending_snapshot) = &pending_snapshot {
+        pending_snapshot.0.seed
+    } else if let Some(level) =
+        campaign_levels(&ldtk_handles, &ldtk_projects).get(current_level_index(&level_selection))
+    {
+        level_seed(&level.iid)
+    } else {
+        warn!("No level selected, seeding the level randomly");
+        game_rng.next_seed()
+    };
+    *game_rng = GameRng::new(seed);
+
+    if let Some(practice_harbour) = practice_harbour {
+        commands.trigger(SpawnHarbour(practice_harbour.seed));
+        return;
+    }
+    let Some(ldtk_handle) = ldtk_handles.get(&LdtkKey::Main) else {
+        warn!("The asset manifest has no main LDtk project, so there is no level to play");
+        next_screen.set(Screen::Title);
+        return;
+    };
     commands
         .spawn(LdtkWorldBundle {
-            ldtk_handle: ldtk_handles[&LdtkKey::Main].clone(),
+            ldtk_handle: ldtk_handle.clone(),
             ..default()
         })
         .insert((Name::new("LdtkLevel"), StateScoped(Screen::Playing)));
 }
 
-fn _spawn_level(
-    _trigger: Trigger<SpawnLevel>,
-    mut commands: Commands,
-    image_handles: Res<HandleMap<ImageKey>>,
-) {
-    commands.trigger(SpawnPlayer);
-
-    let map_size = TilemapSize { x: 30, y: 17 };
-    let tilemap_entity = commands.spawn_empty().id();
-    let mut tile_storage = TileStorage::empty(map_size);
-
-    let tile_parent = commands.spawn(Name::new("Tiles")).id();
-
-    for x in 0..map_size.x {
-        for y in 0..map_size.y {
-            let tile_pos = TilePos { x, y };
-            let (idx, tile_name) = (1, "whomp"); //_get_tile_idx(x, y);
-            let tile_entity = commands
-                .spawn(TileBundle {
-                    position: tile_pos,
-                    tilemap_id: TilemapId(tilemap_entity),
-                    texture_index: TileTextureIndex(idx),
-                    ..Default::default()
-                })
-                .insert(Name::new(format!("Tile-{}", tile_name)))
-                .set_parent(tile_parent)
-                .id();
-            tile_storage.set(&tile_pos, tile_entity);
-        }
-    }
-
-    let tile_size = TilemapTileSize { x: 100.0, y: 100.0 };
-    let grid_size = tile_size.into();
-    let map_type = TilemapType::default();
-
-    let tilemap_texture = image_handles.get(&ImageKey::BasicTileSet).unwrap().clone();
-
-    let tilemap_offset = get_tilemap_center_transform(&map_size, &grid_size, &map_type, 1.0);
-
-    commands
-        .entity(tilemap_entity)
-        .insert(TilemapBundle {
-            grid_size,
-            map_type,
-            size: map_size,
-            storage: tile_storage,
-            texture: TilemapTexture::Single(tilemap_texture),
-            tile_size,
-            transform: tilemap_offset,
-            ..Default::default()
-        })
-        .insert(StateScoped(Screen::Playing));
-
-    commands.insert_resource(TilemapOffset(tilemap_offset));
-}
-
 #[derive(Debug, Clone, Default, Resource, Reflect)]
 #[reflect(Resource)]
 pub struct LevelWalls {
@@ -118,6 +99,15 @@ pub struct LevelWalls {
 }
 
 impl LevelWalls {
+    /// `level_width` and `level_height` are in tiles. Everything outside them counts as wall.
+    pub fn new(wall_locations: HashSet<GridCoords>, level_width: i32, level_height: i32) -> Self {
+        Self {
+            wall_locations,
+            level_width,
+            level_height,
+        }
+    }
+
     pub fn in_wall(&self, grid_coords: &GridCoords) -> bool {
         grid_coords.x < 0
             || grid_coords.y < 0
@@ -125,6 +115,16 @@ impl LevelWalls {
             || grid_coords.y >= self.level_height
             || self.wall_locations.contains(grid_coords)
     }
+
+    /// The level's width in tiles.
+    pub fn width(&self) -> i32 {
+        self.level_width
+    }
+
+    /// The level's height in tiles.
+    pub fn height(&self) -> i32 {
+        self.level_height
+    }
 }
 
 fn cache_wall_locations(
@@ -143,15 +143,112 @@ fn cache_wall_locations(
                 .get_raw_level_by_iid(level_iid.get())
                 .expect("spawned level should exist in project");
 
-            let wall_locations = walls.iter().copied().collect();
+            *level_walls = LevelWalls::new(
+                walls.iter().copied().collect(),
+                level.px_wid / GRID_SIZE,
+                level.px_hei / GRID_SIZE,
+            );
+        }
+    }
+}
+
+/// Per-level rules, read from the LDtk level's fields.
+#[derive(Debug, Clone, Default, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct LevelRules {
+    pub collision_policy: CollisionPolicy,
+    /// Whether every contract has to be completed to clear the level.
+    pub complete_contracts: bool,
+    /// The level is lost once this many cycles have been used.
+    pub cycle_budget: Option<usize>,
+    /// Cycles to beat for a star, if the level has a par.
+    pub par_cycles: Option<usize>,
+    /// Actions to beat for a star, if the level has a par.
+    pub par_actions: Option<usize>,
+    /// The actions each ship has to spend over the level, if it doesn't use the usual set.
+    pub actions: Option<Vec<TurnAction>>,
+}
+
+impl LevelRules {
+    pub fn from_level(level: &Level) -> Self {
+        let collision_policy = level
+            .get_enum_field("CollisionPolicy")
+            .map_err(|e| e.to_string())
+            .and_then(|policy| policy.parse())
+            .unwrap_or_else(|e| {
+                warn!("Falling back to default collision policy: {e}");
+                CollisionPolicy::default()
+            });
+        let complete_contracts = level
+            .get_bool_field("CompleteContracts")
+            .copied()
+            .unwrap_or(false);
+        let cycle_budget = level
+            .get_maybe_int_field("CycleBudget")
+            .ok()
+            .copied()
+            .flatten()
+            .map(|budget| budget.max(0) as usize);
+        let par = |identifier| {
+            level
+                .get_int_field(identifier)
+                .ok()
+                .map(|par| (*par).max(0) as usize)
+        };
+        let actions = level
+            .get_maybe_strings_field("Actions")
+            .ok()
+            .map(|actions| {
+                actions
+                    .iter()
+                    .flatten()
+                    .filter_map(|action| {
+                        action
+                            .parse()
+                            .inspect_err(|e| warn!("Skipping action: {e}"))
+                            .ok()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|actions| !actions.is_empty());
+        Self {
+            collision_policy,
+            complete_contracts,
+            cycle_budget,
+            par_cycles: par("ParCycles"),
+            par_actions: par("ParActions"),
+            actions,
+        }
+    }
+
+    /// The actions each ship starts the level with.
+    pub fn inventory(&self) -> Vec<TurnAction> {
+        self.actions
+            .clone()
+            .unwrap_or_else(|| CycleStore::new().available())
+    }
+}
 
-            let new_level_walls = LevelWalls {
-                wall_locations,
-                level_width: level.px_wid / GRID_SIZE,
-                level_height: level.px_hei / GRID_SIZE,
-            };
+fn read_level_rules(
+    mut level_rules: ResMut<LevelRules>,
+    mut level_events: EventReader<LevelEvent>,
+    mut ship_query: Query<&mut CycleStore, With<Player>>,
+    ldtk_project_entities: Query<&Handle<LdtkProject>>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
+) {
+    for level_event in level_events.read() {
+        if let LevelEvent::Spawned(level_iid) = level_event {
+            let ldtk_project = ldtk_project_assets
+                .get(ldtk_project_entities.single())
+                .expect("LdtkProject should be loaded when level is spawned");
+            let level = ldtk_project
+                .get_raw_level_by_iid(level_iid.get())
+                .expect("spawned level should exist in project");
 
-            *level_walls = new_level_walls;
+            *level_rules = LevelRules::from_level(level);
+            for mut cycle_store in &mut ship_query {
+                *cycle_store = CycleStore::with_actions(level_rules.inventory());
+            }
         }
     }
 }
@@ -5,9 +5,17 @@
 use bevy::prelude::*;
 
 pub mod dock;
+pub mod goal;
+pub mod harbour;
 pub mod level;
 pub mod player;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins((level::plugin, player::plugin, dock::plugin));
+    app.add_plugins((
+        level::plugin,
+        player::plugin,
+        dock::plugin,
+        goal::plugin,
+        harbour::plugin,
+    ));
 }
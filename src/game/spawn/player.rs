@@ -1,56 +1,114 @@
-//! Spawn the player.
+//! Spawn the player's ships from the LDtk `Ship` entities.
 
 use crate::game::assets::AtlasLayoutKey;
-use crate::game::camera::CameraFollow;
-use crate::game::controls::setup_movement_controls;
+use crate::game::assets::{HandleMap, ImageKey};
+use crate::game::cargo::Hold;
+use crate::game::contracts::CargoOrigin;
+use crate::game::game_ui::{CycleStore, SelectedShip};
 use crate::game::movement::{AutoFacingTurn, AutoGridPlacement, Facing};
-use crate::game::spawn::level::GRID_SIZE_V;
-use crate::{
-    game::assets::{HandleMap, ImageKey},
-    screen::Screen,
-};
+use crate::game::rng::{GameRng, RngStream};
 use bevy::prelude::*;
-use bevy_ecs_ldtk::GridCoords;
-use bevy_ecs_tilemap::helpers::square_grid::neighbors::SquareDirection;
-use bevy_ecs_tilemap::tiles::TilePos;
+use bevy_ecs_ldtk::prelude::*;
+use rand::Rng;
 
 pub(super) fn plugin(app: &mut App) {
-    app.observe(spawn_player);
-    app.register_type::<Player>();
+    app.register_ldtk_entity::<ShipBundle>("Ship");
+    app.register_type::<(Player, Hull, ShipNumber)>();
+    app.add_systems(Update, finish_ship_spawn);
 }
 
-#[derive(Event, Debug)]
-pub struct SpawnPlayer;
-
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
 #[reflect(Component)]
 pub struct Player;
 
-fn spawn_player(
-    _trigger: Trigger<SpawnPlayer>,
+/// How many more hits a ship can take from the [`CollisionPolicy::HullDamage`] policy.
+///
+/// [`CollisionPolicy::HullDamage`]: crate::game::simulation::CollisionPolicy::HullDamage
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Hull(pub u32);
+
+impl Default for Hull {
+    fn default() -> Self {
+        Self(3)
+    }
+}
+
+/// A stable number for each ship in the level, starting from 0.
+/// Ships are always resolved in this order, and it is what the player sees in the UI.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Reflect)]
+#[reflect(Component)]
+pub struct ShipNumber(pub usize);
+
+#[derive(Default, Bundle, LdtkEntity)]
+pub struct ShipBundle {
+    player: Player,
+    #[grid_coords]
+    grid_coords: GridCoords,
+    #[from_entity_instance]
+    facing: Facing,
+    hull: Hull,
+    hold: Hold,
+    cargo_origin: CargoOrigin,
+    cycle_store: CycleStore,
+    auto_grid_placement: AutoGridPlacement,
+    auto_facing_turn: AutoFacingTurn,
+}
+
+impl ShipBundle {
+    /// A ship that isn't spawned from LDtk. It still needs an [`EntityIid`] and a transform.
+    pub fn new(grid_coords: GridCoords, facing: Facing) -> Self {
+        Self {
+            grid_coords,
+            facing,
+            ..default()
+        }
+    }
+}
+
+/// LDtk only gives us the logical parts of the ship, so add the sprite and controls
+/// once it has been spawned.
+fn finish_ship_spawn(
     mut commands: Commands,
+    mut new_ships: Query<(Entity, &EntityIid, &mut Transform), Added<Player>>,
+    all_ships: Query<&EntityIid, With<Player>>,
     image_handles: Res<HandleMap<ImageKey>>,
     texture_atlas_layouts: Res<HandleMap<AtlasLayoutKey>>,
+    mut selected_ship: ResMut<SelectedShip>,
+    mut game_rng: ResMut<GameRng>,
 ) {
+    if new_ships.is_empty() {
+        return;
+    }
+    let mut iids: Vec<&EntityIid> = all_ships.iter().collect();
+    iids.sort_by_key(|iid| iid.as_str());
+    // Each ship gets its own shade of the sprite, so they are easier to tell apart.
+    let mut rng = game_rng.fork(RngStream::Cosmetic);
+    let shades: Vec<f32> = iids.iter().map(|_| rng.gen_range(0.75..=1.0)).collect();
+
     let layout = texture_atlas_layouts[&AtlasLayoutKey::BulkLoadVessel].clone();
-    let coords = GridCoords::new(3, 3);
-    let translation = bevy_ecs_ldtk::utils::grid_coords_to_translation(coords, GRID_SIZE_V);
-    commands.spawn((
-        Name::new("Player"),
-        Player,
-        SpriteBundle {
-            texture: image_handles[&ImageKey::BulkLoadVessel].clone(),
-            transform: Transform::from_translation(translation.extend(2.0))
-                .with_scale(Vec3::splat(0.25)),
-            ..Default::default()
-        },
-        TextureAtlas { layout, index: 0 },
-        StateScoped(Screen::Playing),
-        coords,
-        AutoGridPlacement,
-        AutoFacingTurn,
-        Facing::East,
-        setup_movement_controls(),
-        CameraFollow { threshold: 120.0 },
-    ));
+    for (entity, iid, mut transform) in &mut new_ships {
+        let number = iids
+            .iter()
+            .position(|other| *other == iid)
+            .expect("new ship should be in the list of all ships");
+        transform.translation.z = 2.0;
+        transform.scale = Vec3::splat(0.25);
+        commands.entity(entity).insert((
+            Name::new(format!("Ship {}", number + 1)),
+            ShipNumber(number),
+            Sprite {
+                color: Color::srgb(shades[number], shades[number], shades[number]),
+                ..default()
+            },
+            image_handles[&ImageKey::BulkLoadVessel].clone(),
+            TextureAtlas {
+                layout: layout.clone(),
+                index: 0,
+            },
+        ));
+        if number == 0 {
+            selected_ship.0 = Some(entity);
+        }
+    }
 }
@@ -4,6 +4,8 @@ mod game;
 mod screen;
 mod ui;
 
+pub use game::{level_check, solver};
+
 use crate::game::controls::setup_camera_controls;
 use crate::screen::Screen;
 use bevy::{
@@ -67,8 +69,6 @@ impl Plugin for AppPlugin {
             LdtkPlugin,
         ));
 
-        app.init_resource::<game::settings::Settings>();
-
         // Add internal plugins.
         app.add_plugins((game::plugin, screen::plugin, ui::plugin));
 
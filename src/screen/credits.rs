@@ -4,7 +4,7 @@ use bevy::prelude::*;
 
 use super::Screen;
 use crate::{
-    game::{assets::SoundtrackKey, audio::soundtrack::PlaySoundtrack},
+    game::audio::soundtrack::{PlaySoundtrack, Playlist},
     ui::prelude::*,
 };
 
@@ -42,7 +42,7 @@ fn enter_credits(mut commands: Commands) {
             children.button("Back").insert(CreditsAction::Back);
         });
 
-    commands.trigger(PlaySoundtrack::Key(SoundtrackKey::Menus));
+    commands.trigger(PlaySoundtrack::Playlist(Playlist::MENUS));
 }
 
 fn exit_credits(mut commands: Commands) {
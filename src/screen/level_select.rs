@@ -0,0 +1,86 @@
+//! A screen listing every level in the campaign, reachable from the title screen.
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use super::Screen;
+use crate::{
+    game::{
+        assets::{HandleMap, LdtkKey},
+        audio::soundtrack::{PlaySoundtrack, Playlist},
+        campaign::{campaign_levels, Campaign},
+        objectives::MAX_STARS,
+    },
+    ui::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::LevelSelect), enter_level_select);
+    app.add_systems(OnExit(Screen::LevelSelect), exit_level_select);
+
+    app.add_systems(
+        Update,
+        handle_level_select_action.run_if(in_state(Screen::LevelSelect)),
+    );
+    app.register_type::<LevelSelectAction>();
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum LevelSelectAction {
+    Play(usize),
+    Back,
+}
+
+fn enter_level_select(
+    mut commands: Commands,
+    campaign: Res<Campaign>,
+    ldtk_handles: Res<HandleMap<LdtkKey>>,
+    ldtk_projects: Res<Assets<LdtkProject>>,
+) {
+    let levels = campaign_levels(&ldtk_handles, &ldtk_projects);
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::LevelSelect))
+        .with_children(|children| {
+            children.header("Levels");
+            for (index, level) in levels.iter().enumerate() {
+                let name = format!("Level {}", index + 1);
+                if !campaign.is_unlocked(&levels, index) {
+                    children.label(format!("{name} - locked"));
+                    continue;
+                }
+                children.button(name).insert(LevelSelectAction::Play(index));
+                match campaign.best_stars(level) {
+                    Some(stars) => children.label(format!("Completed - {stars}/{MAX_STARS} stars")),
+                    None => children.label("Not completed"),
+                };
+            }
+
+            children.button("Back").insert(LevelSelectAction::Back);
+        });
+
+    commands.trigger(PlaySoundtrack::Playlist(Playlist::MENUS));
+}
+
+fn exit_level_select(mut commands: Commands) {
+    commands.trigger(PlaySoundtrack::Disable);
+}
+
+fn handle_level_select_action(
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut button_query: InteractionQuery<&LevelSelectAction>,
+) {
+    for (interaction, action) in &mut button_query {
+        if matches!(interaction, Interaction::Pressed) {
+            match action {
+                LevelSelectAction::Play(index) => {
+                    *level_selection = LevelSelection::index(*index);
+                    next_screen.set(Screen::Playing);
+                }
+                LevelSelectAction::Back => next_screen.set(Screen::Title),
+            }
+        }
+    }
+}
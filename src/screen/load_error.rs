@@ -0,0 +1,70 @@
+//! The screen shown when assets fail to load, listing what went wrong.
+
+use bevy::prelude::*;
+
+use super::Screen;
+use crate::ui::prelude::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::LoadError), enter_load_error);
+
+    app.register_type::<LoadErrorAction>();
+    app.add_systems(
+        Update,
+        handle_load_error_action.run_if(in_state(Screen::LoadError)),
+    );
+}
+
+/// The paths of the assets that failed to load.
+#[derive(Resource, Debug, Default)]
+pub struct FailedAssets(pub Vec<String>);
+
+/// Assets that are being reloaded after failing, but haven't started loading again yet.
+/// Reloading happens in the background, so until then they still look failed.
+#[derive(Resource, Debug)]
+pub struct RetryingAssets {
+    pub paths: Vec<String>,
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum LoadErrorAction {
+    Retry,
+}
+
+fn enter_load_error(mut commands: Commands, failed_assets: Res<FailedAssets>) {
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::LoadError))
+        .with_children(|children| {
+            children.header("Failed to load");
+            for path in &failed_assets.0 {
+                children.label(path.clone());
+            }
+            children.button("Retry").insert(LoadErrorAction::Retry);
+        });
+}
+
+fn handle_load_error_action(
+    mut commands: Commands,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut button_query: InteractionQuery<&LoadErrorAction>,
+    asset_server: Res<AssetServer>,
+    failed_assets: Res<FailedAssets>,
+) {
+    for (interaction, action) in &mut button_query {
+        if matches!(interaction, Interaction::Pressed) {
+            match action {
+                LoadErrorAction::Retry => {
+                    for path in &failed_assets.0 {
+                        asset_server.reload(path.clone());
+                    }
+                    commands.insert_resource(RetryingAssets {
+                        paths: failed_assets.0.clone(),
+                    });
+                    next_screen.set(Screen::Loading);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! A screen listing the save slots, reachable from the title screen.
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use super::Screen;
+use crate::{
+    game::{
+        assets::{HandleMap, LdtkKey},
+        campaign::campaign_levels,
+        save::{read_snapshot, restore_snapshot, SaveSlot},
+    },
+    ui::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::LoadGame), enter_load_game);
+
+    app.add_systems(
+        Update,
+        handle_load_game_action.run_if(in_state(Screen::LoadGame)),
+    );
+    app.register_type::<LoadGameAction>();
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum LoadGameAction {
+    Load(SaveSlot),
+    Back,
+}
+
+fn enter_load_game(
+    mut commands: Commands,
+    ldtk_handles: Res<HandleMap<LdtkKey>>,
+    ldtk_projects: Res<Assets<LdtkProject>>,
+) {
+    let levels = campaign_levels(&ldtk_handles, &ldtk_projects);
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::LoadGame))
+        .with_children(|children| {
+            children.header("Load game");
+            for slot in SaveSlot::all() {
+                let Some(snapshot) = read_snapshot(slot) else {
+                    continue;
+                };
+                let level = levels
+                    .iter()
+                    .position(|level| level.iid == snapshot.level_iid)
+                    .map_or("Unknown level".to_string(), |index| {
+                        format!("Level {}", index + 1)
+                    });
+                children
+                    .button(slot.to_string())
+                    .insert(LoadGameAction::Load(slot));
+                children.label(format!("{level}, cycle {}", snapshot.cycle_num.cycle() + 1));
+            }
+
+            children.button("Back").insert(LoadGameAction::Back);
+        });
+}
+
+fn handle_load_game_action(
+    mut commands: Commands,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut button_query: InteractionQuery<&LoadGameAction>,
+    ldtk_handles: Res<HandleMap<LdtkKey>>,
+    ldtk_projects: Res<Assets<LdtkProject>>,
+) {
+    for (interaction, action) in &mut button_query {
+        if matches!(interaction, Interaction::Pressed) {
+            match action {
+                LoadGameAction::Load(slot) => {
+                    let levels = campaign_levels(&ldtk_handles, &ldtk_projects);
+                    if let Some(snapshot) = read_snapshot(*slot) {
+                        if restore_snapshot(&mut commands, &mut level_selection, &levels, snapshot)
+                        {
+                            next_screen.set(Screen::Playing);
+                        }
+                    }
+                }
+                LoadGameAction::Back => next_screen.set(Screen::Title),
+            }
+        }
+    }
+}
@@ -1,42 +1,81 @@
 //! A loading screen during which game assets are loaded.
 //! This reduces stuttering, especially for audio on WASM.
 
-use bevy::prelude::*;
+use bevy::{asset::UntypedAssetLoadFailedEvent, prelude::*};
 
 use super::Screen;
 use crate::{
-    game::assets::{HandleMap, ImageKey, SfxKey, SoundtrackKey},
+    game::assets::GameAssets,
+    screen::load_error::{FailedAssets, RetryingAssets},
     ui::prelude::*,
 };
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Screen::Loading), enter_loading);
-    app.add_systems(
-        Update,
-        continue_to_title.run_if(in_state(Screen::Loading).and_then(all_assets_loaded)),
-    );
+    app.add_systems(Update, update_loading.run_if(in_state(Screen::Loading)));
+    app.register_type::<LoadingLabel>();
 }
 
+/// The label showing how many assets have loaded.
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+struct LoadingLabel;
+
 fn enter_loading(mut commands: Commands) {
     commands
         .ui_root()
         .insert(StateScoped(Screen::Loading))
         .with_children(|children| {
-            children.label("Loading...");
+            children.label("Loading...").insert(LoadingLabel);
         });
 }
 
-fn all_assets_loaded(
-    asset_server: Res<AssetServer>,
-    image_handles: Res<HandleMap<ImageKey>>,
-    sfx_handles: Res<HandleMap<SfxKey>>,
-    soundtrack_handles: Res<HandleMap<SoundtrackKey>>,
-) -> bool {
-    image_handles.all_loaded(&asset_server)
-        && sfx_handles.all_loaded(&asset_server)
-        && soundtrack_handles.all_loaded(&asset_server)
-}
+fn update_loading(
+    mut commands: Commands,
+    mut next_screen: ResMut<NextState<Screen>>,
+    retrying_assets: Option<ResMut<RetryingAssets>>,
+    mut failed_events: EventReader<UntypedAssetLoadFailedEvent>,
+    game_assets: GameAssets,
+    label_query: Query<&Children, With<LoadingLabel>>,
+    mut text_query: Query<&mut Text>,
+) {
+    let mut progress = game_assets.progress();
+
+    if let Some(mut retrying_assets) = retrying_assets {
+        // A reload starts in the background, so until it does the asset still looks failed.
+        // Once it has started, or has failed again, its load state can be trusted.
+        let failed_again: Vec<String> = failed_events
+            .read()
+            .map(|event| event.path.to_string())
+            .collect();
+        retrying_assets.paths.retain(|path| {
+            progress.failed.contains(path)
+                && !failed_again.contains(path)
+                && !game_assets.is_loading(path)
+        });
+        if retrying_assets.paths.is_empty() {
+            commands.remove_resource::<RetryingAssets>();
+        } else {
+            progress
+                .failed
+                .retain(|path| !retrying_assets.paths.contains(path));
+        }
+    }
+    if !progress.failed.is_empty() {
+        commands.insert_resource(FailedAssets(progress.failed));
+        next_screen.set(Screen::LoadError);
+        return;
+    }
+    // Until the manifest has been applied, the handle maps are still empty.
+    if game_assets.manifest_applied() && progress.is_done() {
+        next_screen.set(Screen::Title);
+        return;
+    }
 
-fn continue_to_title(mut next_screen: ResMut<NextState<Screen>>) {
-    next_screen.set(Screen::Title);
+    for children in &label_query {
+        let mut text_iter = text_query.iter_many_mut(children);
+        while let Some(mut text) = text_iter.fetch_next() {
+            text.sections[0].value = format!("Loading... {}/{}", progress.loaded, progress.total);
+        }
+    }
 }
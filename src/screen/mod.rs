@@ -1,8 +1,13 @@
 //! The game's main screen states and transitions between them.
 
 mod credits;
+mod level_select;
+mod load_error;
+mod load_game;
 mod loading;
 mod playing;
+mod replay;
+mod results;
 mod settings;
 mod splash;
 mod title;
@@ -12,14 +17,21 @@ use bevy::prelude::*;
 pub(super) fn plugin(app: &mut App) {
     app.init_state::<Screen>();
     app.enable_state_scoped_entities::<Screen>();
+    app.add_sub_state::<PlayingState>();
+    app.enable_state_scoped_entities::<PlayingState>();
 
     app.add_plugins((
         splash::plugin,
         loading::plugin,
+        load_error::plugin,
         title::plugin,
         credits::plugin,
+        level_select::plugin,
+        load_game::plugin,
         playing::plugin,
         settings::plugin,
+        results::plugin,
+        replay::plugin,
     ));
 }
 
@@ -29,8 +41,30 @@ pub enum Screen {
     #[default]
     Splash,
     Loading,
+    LoadError,
     Title,
+    LevelSelect,
+    LoadGame,
     Credits,
     Playing,
+    /// Passes straight back to [`Screen::Playing`], so restarting spawns the level afresh.
+    Restart,
     Settings,
+    Results,
+    Replay,
+}
+
+/// The phases of playing a level.
+#[derive(SubStates, Debug, Hash, PartialEq, Eq, Clone, Copy, Default)]
+#[source(Screen = Screen::Playing)]
+pub enum PlayingState {
+    /// Programming the ships for the next cycle.
+    #[default]
+    Planning,
+    /// Playing the cycle's programs out. They can't be edited until it finishes.
+    Executing,
+    Paused,
+    /// The level has ended, and the results screen is about to be shown.
+    Won,
+    Lost,
 }
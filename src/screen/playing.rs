@@ -1,33 +1,234 @@
 //! The screen state for the main game loop.
 
 use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use bevy_egui::{egui, EguiContexts};
 
-use super::Screen;
+use super::{settings::settings_panel, PlayingState, Screen};
 use crate::game::{
-    assets::SoundtrackKey, audio::soundtrack::PlaySoundtrack, spawn::level::SpawnLevel,
+    audio::soundtrack::{PlaySoundtrack, Playlist},
+    save::{SaveGame, SaveSlot},
+    settings::Settings,
+    spawn::{harbour::PracticeHarbour, level::SpawnLevel},
 };
 
 pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<PauseMenuPage>();
     app.add_systems(OnEnter(Screen::Playing), enter_playing);
     app.add_systems(OnExit(Screen::Playing), exit_playing);
+    app.add_systems(OnEnter(Screen::Restart), restart_level);
+    app.add_systems(OnEnter(PlayingState::Paused), reset_pause_menu);
 
     app.add_systems(
         Update,
-        return_to_title_screen
-            .run_if(in_state(Screen::Playing).and_then(input_just_pressed(KeyCode::Escape))),
+        (
+            toggle_pause
+                .run_if(in_state(Screen::Playing).and_then(input_just_pressed(KeyCode::Escape))),
+            pause_menu
+                .run_if(in_state(PlayingState::Paused).and_then(resource_exists::<PausedFrom>)),
+        ),
     );
 }
 
 fn enter_playing(mut commands: Commands) {
     commands.trigger(SpawnLevel);
-    commands.trigger(PlaySoundtrack::Key(SoundtrackKey::Gameplay));
+    commands.trigger(PlaySoundtrack::Playlist(Playlist::GAMEPLAY));
 }
 
-fn exit_playing(mut commands: Commands) {
+fn exit_playing(
+    mut commands: Commands,
+    mut transitions: EventReader<StateTransitionEvent<Screen>>,
+) {
+    // Restarting comes straight back, so keep the playlist going rather than fading it out.
+    let restarting = transitions
+        .read()
+        .last()
+        .is_some_and(|transition| transition.entered == Some(Screen::Restart));
+    if restarting {
+        return;
+    }
     // We could use [`StateScoped`] on the sound playing entities instead.
     commands.trigger(PlaySoundtrack::Disable);
 }
 
-fn return_to_title_screen(mut next_screen: ResMut<NextState<Screen>>) {
-    next_screen.set(Screen::Title);
+fn restart_level(mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Playing);
+}
+
+/// The phase to go back to when the game is resumed.
+#[derive(Resource, Debug)]
+struct PausedFrom(PlayingState);
+
+fn toggle_pause(
+    mut commands: Commands,
+    playing_state: Res<State<PlayingState>>,
+    paused_from: Option<Res<PausedFrom>>,
+    mut next_playing_state: ResMut<NextState<PlayingState>>,
+) {
+    match playing_state.get() {
+        PlayingState::Planning | PlayingState::Executing => {
+            commands.insert_resource(PausedFrom(*playing_state.get()));
+            next_playing_state.set(PlayingState::Paused);
+        }
+        PlayingState::Paused => {
+            if let Some(paused_from) = paused_from {
+                next_playing_state.set(paused_from.0);
+            }
+        }
+        // The level is already over.
+        PlayingState::Won | PlayingState::Lost => {}
+    }
+}
+
+/// Which part of the pause menu is showing.
+#[derive(Resource, Debug, Default, PartialEq, Eq)]
+enum PauseMenuPage {
+    #[default]
+    Main,
+    Settings,
+    ConfirmQuit,
+}
+
+fn reset_pause_menu(mut page: ResMut<PauseMenuPage>) {
+    *page = PauseMenuPage::Main;
+}
+
+fn pause_menu(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut page: ResMut<PauseMenuPage>,
+    paused_from: Res<PausedFrom>,
+    practice_harbour: Option<Res<PracticeHarbour>>,
+    mut settings: ResMut<Settings>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut next_playing_state: ResMut<NextState<PlayingState>>,
+) {
+    egui::Window::new("Paused")
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .collapsible(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| match *page {
+            PauseMenuPage::Main => {
+                if ui.button("Resume").clicked() {
+                    next_playing_state.set(paused_from.0);
+                }
+                if ui.button("Restart level").clicked() {
+                    // Leaving `Screen::Playing` despawns the level, and coming back spawns it afresh.
+                    next_screen.set(Screen::Restart);
+                }
+                if ui.button("Settings").clicked() {
+                    *page = PauseMenuPage::Settings;
+                }
+                if ui.button("Quit to title").clicked() {
+                    *page = PauseMenuPage::ConfirmQuit;
+                }
+            }
+            PauseMenuPage::Settings => {
+                settings_panel(ui, &mut settings);
+                if ui.button("Back").clicked() {
+                    *page = PauseMenuPage::Main;
+                }
+            }
+            PauseMenuPage::ConfirmQuit => {
+                // Generated harbours can't be saved, so there's nothing to come back to.
+                if practice_harbour.is_some() {
+                    ui.label("Quit to the title screen? This harbour will be lost.");
+                } else {
+                    ui.label("Quit to the title screen?");
+                }
+                ui.horizontal(|ui| {
+                    if practice_harbour.is_none()
+                        && ui.button("Save to autosave and quit").clicked()
+                    {
+                        commands.trigger(SaveGame(SaveSlot::Auto));
+                        next_screen.set(Screen::Title);
+                    }
+                    if ui.button("Quit without saving").clicked() {
+                        next_screen.set(Screen::Title);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        *page = PauseMenuPage::Main;
+                    }
+                });
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{
+        cargo::{CargoType, Hold},
+        game_ui::{CycleStore, TurnAction},
+        movement::Facing,
+        spawn::player::ShipBundle,
+    };
+    use bevy::state::app::StatesPlugin;
+    use bevy_ecs_ldtk::GridCoords;
+
+    /// How many times the soundtrack has been disabled.
+    #[derive(Resource, Default)]
+    struct SoundtrackDisables(usize);
+
+    fn count_disables(trigger: Trigger<PlaySoundtrack>, mut disables: ResMut<SoundtrackDisables>) {
+        if matches!(trigger.event(), PlaySoundtrack::Disable) {
+            disables.0 += 1;
+        }
+    }
+
+    const START: GridCoords = GridCoords { x: 1, y: 1 };
+
+    /// Stands in for the level, with a single ship.
+    fn spawn_ship(_trigger: Trigger<SpawnLevel>, mut commands: Commands) {
+        commands.spawn((
+            ShipBundle::new(START, Facing::East),
+            StateScoped(Screen::Playing),
+        ));
+    }
+
+    #[test]
+    fn restart_respawns_the_level() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin));
+        app.init_state::<Screen>();
+        app.enable_state_scoped_entities::<Screen>();
+        app.add_sub_state::<PlayingState>();
+        app.init_resource::<ButtonInput<KeyCode>>();
+        app.add_plugins(plugin);
+        app.observe(spawn_ship);
+        app.init_resource::<SoundtrackDisables>();
+        app.observe(count_disables);
+
+        app.world_mut()
+            .resource_mut::<NextState<Screen>>()
+            .set(Screen::Playing);
+        app.update();
+
+        // Play the ship on a bit.
+        let mut ships = app
+            .world_mut()
+            .query::<(&mut GridCoords, &mut Hold, &mut CycleStore)>();
+        let (mut coords, mut hold, mut cycle_store) = ships.single_mut(app.world_mut());
+        *coords = GridCoords::new(4, 2);
+        hold.cargo = Some(CargoType::Grain);
+        hold.amount = 4;
+        cycle_store.set_program(&[TurnAction::Forward, TurnAction::Load]);
+
+        app.world_mut()
+            .resource_mut::<NextState<Screen>>()
+            .set(Screen::Restart);
+        app.update();
+        app.update();
+
+        assert_eq!(
+            app.world().resource::<State<Screen>>().get(),
+            &Screen::Playing
+        );
+        let (coords, hold, cycle_store) = ships.single(app.world());
+        assert_eq!(*coords, START);
+        assert_eq!(*hold, Hold::default());
+        assert_eq!(cycle_store.available(), CycleStore::new().available());
+        assert!(cycle_store.turn_actions().0.iter().all(Option::is_none));
+        // The gameplay playlist keeps running through the restart.
+        assert_eq!(app.world().resource::<SoundtrackDisables>().0, 0);
+    }
 }
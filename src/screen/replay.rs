@@ -0,0 +1,259 @@
+//! A screen that plays a recorded session back, drawn straight from the replay's frames
+//! rather than by spawning the level.
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_egui::egui::{self, Align2, Color32, FontId, Pos2, Rect, Stroke};
+use bevy_egui::EguiContexts;
+
+use super::Screen;
+use crate::game::{
+    assets::{HandleMap, LdtkKey},
+    audio::soundtrack::{PlaySoundtrack, Playlist},
+    level_check::{read_level, LevelLayout},
+    replay::{run_replay, Replay, ReplayFrame, ReplayLevel, ReplayRun},
+    settings::Settings,
+    simulation::PROGRAM_LENGTH,
+    spawn::harbour::generate_harbour,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        OnEnter(Screen::Replay),
+        enter_replay.run_if(resource_exists::<ViewReplay>),
+    );
+    app.add_systems(OnExit(Screen::Replay), exit_replay);
+    app.add_systems(
+        Update,
+        (tick_replay, replay_ui)
+            .chain()
+            .run_if(in_state(Screen::Replay).and_then(resource_exists::<ReplayViewer>)),
+    );
+}
+
+/// The replay to show when [`Screen::Replay`] is entered.
+#[derive(Resource, Debug)]
+pub(super) struct ViewReplay(pub Replay);
+
+#[derive(Resource, Debug)]
+struct ReplayViewer {
+    run: Result<ReplayRun, String>,
+    /// Index into the run's frames.
+    frame: usize,
+    playing: bool,
+    timer: Timer,
+}
+
+const LAND_COLOUR: Color32 = Color32::from_rgb(96, 152, 72);
+const WATER_COLOUR: Color32 = Color32::from_rgb(56, 104, 176);
+const DOCK_COLOUR: Color32 = Color32::from_rgb(64, 64, 64);
+const SHIP_COLOUR: Color32 = Color32::from_rgb(232, 224, 208);
+
+fn enter_replay(
+    mut commands: Commands,
+    view_replay: Res<ViewReplay>,
+    ldtk_handles: Res<HandleMap<LdtkKey>>,
+    ldtk_projects: Res<Assets<LdtkProject>>,
+    settings: Res<Settings>,
+) {
+    let replay = &view_replay.0;
+    let layout = match &replay.level {
+        ReplayLevel::Harbour(seed) => Ok(generate_harbour(*seed).level_layout()),
+        ReplayLevel::Campaign(iid) => ldtk_handles
+            .get(&LdtkKey::Main)
+            .and_then(|handle| ldtk_projects.get(handle))
+            .and_then(|project| project.iter_raw_levels().find(|level| level.iid == *iid))
+            .map(read_level)
+            .ok_or_else(|| "its level is no longer in the game".to_string()),
+    };
+    commands.insert_resource(ReplayViewer {
+        run: layout.and_then(|layout| run_replay(replay, layout)),
+        frame: 0,
+        playing: true,
+        timer: Timer::from_seconds(settings.step_delay, TimerMode::Repeating),
+    });
+    commands.trigger(PlaySoundtrack::Playlist(Playlist::MENUS));
+}
+
+fn exit_replay(mut commands: Commands) {
+    commands.remove_resource::<ReplayViewer>();
+    commands.remove_resource::<ViewReplay>();
+    commands.trigger(PlaySoundtrack::Disable);
+}
+
+fn tick_replay(time: Res<Time>, mut viewer: ResMut<ReplayViewer>) {
+    let viewer = &mut *viewer;
+    let Ok(run) = &viewer.run else {
+        return;
+    };
+    if !viewer.playing || !viewer.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    if viewer.frame + 1 < run.frames.len() {
+        viewer.frame += 1;
+    } else {
+        viewer.playing = false;
+    }
+}
+
+fn replay_ui(
+    mut contexts: EguiContexts,
+    mut viewer: ResMut<ReplayViewer>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    let ctx = contexts.ctx_mut();
+    let viewer = &mut *viewer;
+    let run = match &viewer.run {
+        Ok(run) => run,
+        Err(e) => {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.label(format!("Can't play this replay: {e}"));
+                if ui.button("Back").clicked() {
+                    next_screen.set(Screen::Title);
+                }
+            });
+            return;
+        }
+    };
+    let last = run.frames.len() - 1;
+
+    egui::TopBottomPanel::bottom("replay_controls").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            let play_label = if viewer.playing { "Pause" } else { "Play" };
+            if ui.button(play_label).clicked() {
+                // Playing from the end starts again from the beginning.
+                if !viewer.playing && viewer.frame == last {
+                    viewer.frame = 0;
+                }
+                viewer.playing = !viewer.playing;
+                viewer.timer.reset();
+            }
+            if ui.button("Step").clicked() {
+                viewer.playing = false;
+                viewer.frame = (viewer.frame + 1).min(last);
+            }
+            ui.add(egui::Slider::new(&mut viewer.frame, 0..=last).show_value(false));
+            let frame = &run.frames[viewer.frame];
+            ui.label(match frame.step {
+                Some(step) => format!(
+                    "Cycle {}, step {}/{}",
+                    frame.cycle + 1,
+                    step + 1,
+                    PROGRAM_LENGTH
+                ),
+                None => "Start".to_string(),
+            });
+        });
+        ui.horizontal(|ui| {
+            if run.checksum_matches {
+                ui.label("Ends the same as when it was recorded");
+            } else {
+                ui.colored_label(
+                    Color32::LIGHT_RED,
+                    "Doesn't end the same as when it was recorded. The level may have changed since.",
+                );
+            }
+            if ui.button("Back").clicked() {
+                next_screen.set(Screen::Title);
+            }
+        });
+    });
+
+    egui::CentralPanel::default().show(ctx, |ui| {
+        draw_frame(ui, &run.layout, &run.frames[viewer.frame]);
+    });
+}
+
+/// Draw the level as a grid of tiles, with the docks and ships where they are in `frame`.
+fn draw_frame(ui: &mut egui::Ui, layout: &LevelLayout, frame: &ReplayFrame) {
+    let (width, height) = (layout.walls.width(), layout.walls.height());
+    let (response, painter) = ui.allocate_painter(ui.available_size(), egui::Sense::hover());
+    let cell = (response.rect.width() / width as f32).min(response.rect.height() / height as f32);
+    let origin =
+        response.rect.center() - egui::vec2(width as f32 * cell, height as f32 * cell) / 2.0;
+    // Grid coordinates count up the screen, while egui's count down.
+    let cell_rect = |coords: GridCoords| {
+        Rect::from_min_size(
+            origin + egui::vec2(coords.x as f32, (height - 1 - coords.y) as f32) * cell,
+            egui::Vec2::splat(cell),
+        )
+    };
+    let direction = |offset: IVec2| egui::vec2(offset.x as f32, -offset.y as f32);
+
+    for y in 0..height {
+        for x in 0..width {
+            let coords = GridCoords::new(x, y);
+            let colour = if layout.walls.in_wall(&coords) {
+                LAND_COLOUR
+            } else {
+                WATER_COLOUR
+            };
+            painter.rect_filled(cell_rect(coords), 0.0, colour);
+        }
+    }
+
+    for dock in &frame.docks {
+        let center = cell_rect(dock.coords).center();
+        let reach = direction(dock.facing.to_offset()) * cell * 0.5;
+        painter.line_segment(
+            [center, center + reach],
+            Stroke::new(cell * 0.1, DOCK_COLOUR),
+        );
+        painter.rect_filled(
+            Rect::from_center_size(center, egui::Vec2::splat(cell * 0.6)),
+            2.0,
+            DOCK_COLOUR,
+        );
+        if dock.stock.cargo.is_some() {
+            label(
+                &painter,
+                center,
+                dock.stock.amount.to_string(),
+                cell,
+                Color32::WHITE,
+            );
+        }
+    }
+
+    for (number, ship) in frame.ships.iter().enumerate() {
+        let center = cell_rect(ship.coords).center();
+        let forward = direction(ship.facing.to_offset()) * cell * 0.4;
+        let side = egui::vec2(-forward.y, forward.x) * 0.6;
+        painter.add(egui::Shape::convex_polygon(
+            vec![
+                center + forward,
+                center - forward + side,
+                center - forward - side,
+            ],
+            SHIP_COLOUR,
+            Stroke::new(1.0, Color32::BLACK),
+        ));
+        label(
+            &painter,
+            center,
+            (number + 1).to_string(),
+            cell,
+            Color32::BLACK,
+        );
+        if ship.hold.amount > 0 {
+            let corner = center + egui::vec2(cell * 0.35, -cell * 0.35);
+            label(
+                &painter,
+                corner,
+                ship.hold.amount.to_string(),
+                cell,
+                Color32::WHITE,
+            );
+        }
+    }
+}
+
+fn label(painter: &egui::Painter, at: Pos2, text: String, cell: f32, colour: Color32) {
+    painter.text(
+        at,
+        Align2::CENTER_CENTER,
+        text,
+        FontId::proportional(cell * 0.3),
+        colour,
+    );
+}
@@ -0,0 +1,158 @@
+//! The screen shown when a level is won or lost.
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_egui::EguiContexts;
+
+use super::{replay::ViewReplay, Screen};
+use crate::{
+    game::{
+        assets::{HandleMap, LdtkKey},
+        audio::soundtrack::{PlaySoundtrack, Playlist},
+        campaign::{campaign_levels, current_level_index},
+        daily::{record_daily_run, DailyChallenge, DailyRun, DailyRuns},
+        objectives::{LevelResult, MAX_STARS},
+        replay::LastReplay,
+        rng::GameRng,
+        spawn::harbour::PracticeHarbour,
+    },
+    ui::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    // Record the run first so it counts towards today's best.
+    app.add_systems(
+        OnEnter(Screen::Results),
+        enter_results.after(record_daily_run),
+    );
+    app.add_systems(OnExit(Screen::Results), exit_results);
+
+    app.add_systems(
+        Update,
+        handle_results_action.run_if(in_state(Screen::Results)),
+    );
+    app.register_type::<ResultsAction>();
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum ResultsAction {
+    Retry,
+    NextLevel,
+    NewHarbour,
+    /// Copy the daily challenge result string to the clipboard.
+    CopyResult,
+    WatchReplay,
+    Title,
+}
+
+fn enter_results(
+    mut commands: Commands,
+    level_result: Res<LevelResult>,
+    level_selection: Res<LevelSelection>,
+    ldtk_handles: Res<HandleMap<LdtkKey>>,
+    ldtk_projects: Res<Assets<LdtkProject>>,
+    practice_harbour: Option<Res<PracticeHarbour>>,
+    daily_challenge: Option<Res<DailyChallenge>>,
+    daily_runs: Res<DailyRuns>,
+    last_replay: Option<Res<LastReplay>>,
+) {
+    let level_count = campaign_levels(&ldtk_handles, &ldtk_projects).len();
+    let has_next_level = current_level_index(&level_selection) + 1 < level_count;
+
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::Results))
+        .with_children(|children| {
+            if level_result.won {
+                children.header("Harbour cleared!");
+            } else {
+                children.header("Level failed");
+            }
+            children.label(level_result.reason.clone());
+            children.label(format!("Cycles used: {}", level_result.cycles));
+            children.label(format!("Actions spent: {}", level_result.actions));
+            if level_result.won {
+                children.label(format!("Stars: {}/{}", level_result.stars, MAX_STARS));
+            }
+
+            if let Some(challenge) = &daily_challenge {
+                children.label(DailyRun::new(challenge, &level_result).result_string());
+                if let Some(best) = daily_runs.best(challenge.day) {
+                    children.label(format!("Best today: {}", best.result_string()));
+                }
+                children
+                    .button("Copy result")
+                    .insert(ResultsAction::CopyResult);
+            }
+
+            children.button("Retry").insert(ResultsAction::Retry);
+            if daily_challenge.is_some() {
+                // Everyone gets the same harbour, so there isn't another one to try.
+            } else if practice_harbour.is_some() {
+                children
+                    .button("New harbour")
+                    .insert(ResultsAction::NewHarbour);
+            } else if level_result.won && has_next_level {
+                children
+                    .button("Next level")
+                    .insert(ResultsAction::NextLevel);
+            }
+            if last_replay.is_some() {
+                children
+                    .button("Watch replay")
+                    .insert(ResultsAction::WatchReplay);
+            }
+            children.button("Title").insert(ResultsAction::Title);
+        });
+
+    commands.trigger(PlaySoundtrack::Playlist(Playlist::MENUS));
+}
+
+fn exit_results(mut commands: Commands) {
+    commands.trigger(PlaySoundtrack::Disable);
+}
+
+fn handle_results_action(
+    mut commands: Commands,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut button_query: InteractionQuery<&ResultsAction>,
+    mut contexts: EguiContexts,
+    level_result: Res<LevelResult>,
+    daily_challenge: Option<Res<DailyChallenge>>,
+    last_replay: Option<Res<LastReplay>>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    for (interaction, action) in &mut button_query {
+        if matches!(interaction, Interaction::Pressed) {
+            match action {
+                ResultsAction::Retry => next_screen.set(Screen::Playing),
+                ResultsAction::NextLevel => {
+                    let next = current_level_index(&level_selection) + 1;
+                    *level_selection = LevelSelection::index(next);
+                    next_screen.set(Screen::Playing);
+                }
+                ResultsAction::NewHarbour => {
+                    commands.insert_resource(PracticeHarbour::random(&mut game_rng));
+                    next_screen.set(Screen::Playing);
+                }
+                ResultsAction::CopyResult => {
+                    if let Some(challenge) = &daily_challenge {
+                        let result = DailyRun::new(challenge, &level_result).result_string();
+                        contexts
+                            .ctx_mut()
+                            .output_mut(|output| output.copied_text = result);
+                    }
+                }
+                ResultsAction::WatchReplay => {
+                    if let Some(last_replay) = &last_replay {
+                        commands.insert_resource(ViewReplay(last_replay.0.clone()));
+                        next_screen.set(Screen::Replay);
+                    }
+                }
+                ResultsAction::Title => next_screen.set(Screen::Title),
+            }
+        }
+    }
+}
@@ -2,7 +2,13 @@
 
 use super::Screen;
 use crate::{
-    game::{assets::SoundtrackKey, audio::soundtrack::PlaySoundtrack},
+    game::{
+        audio::{
+            soundtrack::{PlaySoundtrack, Playlist},
+            AudioChannel,
+        },
+        settings::{DisplayMode, Settings, RESOLUTIONS},
+    },
     ui::prelude::*,
 };
 use bevy::prelude::*;
@@ -23,7 +29,7 @@ enum SettingsAction {
 }
 
 fn enter_credits(mut commands: Commands) {
-    commands.trigger(PlaySoundtrack::Key(SoundtrackKey::Menus));
+    commands.trigger(PlaySoundtrack::Playlist(Playlist::MENUS));
 }
 
 fn exit_credits(mut commands: Commands) {
@@ -33,7 +39,7 @@ fn exit_credits(mut commands: Commands) {
 fn update_settings(
     mut next_screen: ResMut<NextState<Screen>>,
     mut contexts: EguiContexts,
-    mut settings: ResMut<crate::game::settings::Settings>,
+    mut settings: ResMut<Settings>,
 ) {
     egui::CentralPanel::default().show(contexts.ctx_mut(), |ui| {
         ui.with_layout(
@@ -41,22 +47,7 @@ fn update_settings(
             |ui| {
                 ui.vertical(|ui| {
                     ui.heading("Settings");
-
-                    ui.horizontal(|ui| {
-                        ui.label("Volume");
-                        ui.add(egui::Slider::new(&mut settings.volume, 0.0..=1.0));
-                    });
-
-                    ui.horizontal(|ui| {
-                        ui.label("Music");
-                        ui.add(egui::Checkbox::new(&mut settings.music, ""));
-                    });
-
-                    ui.horizontal(|ui| {
-                        ui.label("Fullscreen");
-                        ui.checkbox(&mut true, "");
-                    });
-
+                    settings_panel(ui, &mut settings);
                     if ui.button("Back").clicked() {
                         next_screen.set(Screen::Title);
                     }
@@ -65,3 +56,64 @@ fn update_settings(
         );
     });
 }
+
+/// The rows of settings, shared by the settings screen and the pause menu.
+pub(super) fn settings_panel(ui: &mut egui::Ui, settings: &mut ResMut<Settings>) {
+    // Edit a copy so the settings are only marked as changed (and saved) when something changes.
+    let mut edited = settings.clone();
+
+    for channel in AudioChannel::ALL {
+        let channel_volume = edited.channel_mut(channel);
+        ui.horizontal(|ui| {
+            ui.label(format!("{channel} volume"));
+            ui.add_enabled(
+                !channel_volume.muted,
+                egui::Slider::new(&mut channel_volume.volume, 0.0..=1.0),
+            );
+            ui.checkbox(&mut channel_volume.muted, "Mute");
+        });
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Step delay");
+        ui.add(egui::Slider::new(&mut edited.step_delay, 0.1..=2.0).suffix("s"));
+    });
+
+    #[cfg(not(target_family = "wasm"))]
+    {
+        ui.horizontal(|ui| {
+            ui.label("Display mode");
+            egui::ComboBox::from_id_source("display_mode")
+                .selected_text(edited.display_mode.to_string())
+                .show_ui(ui, |ui| {
+                    for mode in DisplayMode::ALL {
+                        ui.selectable_value(&mut edited.display_mode, mode, mode.to_string());
+                    }
+                });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Resolution");
+            let label = |(width, height): (u32, u32)| format!("{width}x{height}");
+            egui::ComboBox::from_id_source("resolution")
+                .selected_text(label(edited.resolution))
+                .show_ui(ui, |ui| {
+                    for resolution in RESOLUTIONS {
+                        ui.selectable_value(&mut edited.resolution, resolution, label(resolution));
+                    }
+                });
+        });
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("VSync");
+        ui.checkbox(&mut edited.vsync, "");
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("UI scale");
+        ui.add(egui::Slider::new(&mut edited.ui_scale, 0.5..=2.0));
+    });
+
+    settings.set_if_neq(edited);
+}
@@ -2,8 +2,16 @@
 
 use bevy::prelude::*;
 
-use super::Screen;
+use super::{replay::ViewReplay, Screen};
+use crate::game::assets::{HandleMap, LdtkKey};
+use crate::game::campaign::{campaign_levels, Campaign};
+use crate::game::daily::DailyChallenge;
+use crate::game::replay::latest_replay;
+use crate::game::rng::GameRng;
+use crate::game::save::{latest_snapshot, restore_snapshot};
+use crate::game::spawn::harbour::PracticeHarbour;
 use crate::ui::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Screen::Title), enter_title);
@@ -15,7 +23,13 @@ pub(super) fn plugin(app: &mut App) {
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
 #[reflect(Component)]
 enum TitleAction {
+    Continue,
     Play,
+    Levels,
+    Practice,
+    Daily,
+    Load,
+    Replay,
     Credits,
     Settings,
     /// Exit doesn't work well with embedded applications.
@@ -28,7 +42,22 @@ fn enter_title(mut commands: Commands) {
         .ui_root()
         .insert(StateScoped(Screen::Title))
         .with_children(|children| {
+            let has_save = latest_snapshot().is_some();
+            if has_save {
+                children.button("Continue").insert(TitleAction::Continue);
+            }
             children.button("Play").insert(TitleAction::Play);
+            children.button("Levels").insert(TitleAction::Levels);
+            children.button("Practice").insert(TitleAction::Practice);
+            children
+                .button("Daily Challenge")
+                .insert(TitleAction::Daily);
+            if has_save {
+                children.button("Load").insert(TitleAction::Load);
+            }
+            if latest_replay().is_some() {
+                children.button("Last replay").insert(TitleAction::Replay);
+            }
             children.button("Credits").insert(TitleAction::Credits);
             children.button("Settings").insert(TitleAction::Settings);
 
@@ -38,14 +67,51 @@ fn enter_title(mut commands: Commands) {
 }
 
 fn handle_title_action(
+    mut commands: Commands,
     mut next_screen: ResMut<NextState<Screen>>,
     mut button_query: InteractionQuery<&TitleAction>,
+    mut level_selection: ResMut<LevelSelection>,
+    campaign: Res<Campaign>,
+    ldtk_handles: Res<HandleMap<LdtkKey>>,
+    ldtk_projects: Res<Assets<LdtkProject>>,
+    mut game_rng: ResMut<GameRng>,
     #[cfg(not(target_family = "wasm"))] mut app_exit: EventWriter<AppExit>,
 ) {
     for (interaction, action) in &mut button_query {
         if matches!(interaction, Interaction::Pressed) {
             match action {
-                TitleAction::Play => next_screen.set(Screen::Playing),
+                TitleAction::Continue => {
+                    let levels = campaign_levels(&ldtk_handles, &ldtk_projects);
+                    if let Some((_, snapshot)) = latest_snapshot() {
+                        if restore_snapshot(&mut commands, &mut level_selection, &levels, snapshot)
+                        {
+                            next_screen.set(Screen::Playing);
+                        }
+                    }
+                }
+                TitleAction::Play => {
+                    let levels = campaign_levels(&ldtk_handles, &ldtk_projects);
+                    *level_selection = LevelSelection::index(campaign.next_level(&levels));
+                    next_screen.set(Screen::Playing);
+                }
+                TitleAction::Levels => next_screen.set(Screen::LevelSelect),
+                TitleAction::Practice => {
+                    commands.insert_resource(PracticeHarbour::random(&mut game_rng));
+                    next_screen.set(Screen::Playing);
+                }
+                TitleAction::Daily => {
+                    let challenge = DailyChallenge::today();
+                    commands.insert_resource(challenge.harbour());
+                    commands.insert_resource(challenge);
+                    next_screen.set(Screen::Playing);
+                }
+                TitleAction::Load => next_screen.set(Screen::LoadGame),
+                TitleAction::Replay => {
+                    if let Some(replay) = latest_replay() {
+                        commands.insert_resource(ViewReplay(replay));
+                        next_screen.set(Screen::Replay);
+                    }
+                }
                 TitleAction::Credits => next_screen.set(Screen::Credits),
                 TitleAction::Settings => next_screen.set(Screen::Settings),
 